@@ -1,18 +1,64 @@
+use std::collections::HashMap;
 use std::sync::OnceLock;
 use std::time::Duration;
 
 use tracing::{error};
 
 use super::error::Result;
+use crate::time::parse_duration;
 
-use lib_utils::env::{get_env, get_env_duration};
+use lib_utils::env::{get_env, get_env_duration, get_env_parse};
 
 pub struct Config {
     pub development: String,
 
+    // Storage
+    pub storage: StorageBackend,
+    pub sqlite_path: String,
+    pub db_health_interval: Duration,
+
     // Scheduler
     pub sch_interval: Duration,
+    /// Percents of the SLA after which the cleaner warns, e.g. `80,95`.
+    pub sla_warning_thresholds: Vec<u64>,
+    pub sla_warning_webhook: bool,
+    /// Pause after a failed task run, doubled on every consecutive failure.
+    pub scheduler_backoff: Duration,
+    /// How long the scheduler leader holds `flowlocker/scheduler` without renewing it.
+    pub scheduler_lease: Duration,
+    /// How long finished processes are kept before the `retention` task deletes them.
+    pub retention: Duration,
+    pub rollup_interval: Duration,
+    /// Where `archive_export` writes finished locks, the task is off when unset.
+    pub archive_dir: Option<String>,
+    /// Cron expression, with seconds, for `archive_export`.
+    pub archive_schedule: String,
+    /// `expiry` and `retention` only log the processes they would outdate or delete.
+    pub cleaner_dry_run: bool,
+
+    // Locks
+    /// Cooldown in seconds per `app/process`, e.g. `billing/sync=10m,crm/import=1h`.
+    pub cooldowns: HashMap<String, u64>,
+
+    // gRPC
+    pub grpc_port: u16,
+
+    // WebSocket lock sessions
+    pub ws_heartbeat_timeout: Duration,
 
+    // Webhooks
+    pub webhook_max_attempts: u32,
+    pub webhook_backoff: Duration,
+
+    // Shutdown
+    /// How long servers and the scheduler get to drain once a stop signal arrives.
+    pub shutdown_timeout: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageBackend {
+    SurrealDB,
+    Sqlite,
 }
 
 pub fn config() -> &'static Config {
@@ -30,11 +76,98 @@ impl Config {
     fn load_from_env() -> Result<Config> {
         let interval: Duration = get_env_duration("SCHEDULER_INTERVAL")?;
 
+        let storage = match get_env("STORAGE_BACKEND").unwrap_or_default().as_str() {
+            "" | "surrealdb" => StorageBackend::SurrealDB,
+            "sqlite" => StorageBackend::Sqlite,
+            other => return Err(format!("unknown STORAGE_BACKEND: {other}").into()),
+        };
+
         let config = Config {
             development: get_env("DEVELOPMENT").unwrap_or_else(|_| "".to_string()),
+            storage,
+            sqlite_path: get_env("SQLITE_PATH").unwrap_or_else(|_| "flowlocker.db".to_string()),
+            db_health_interval: non_zero("DB_HEALTH_INTERVAL", get_env_duration("DB_HEALTH_INTERVAL").unwrap_or(Duration::from_secs(5)))?,
             sch_interval: interval,
+            sla_warning_thresholds: parse_thresholds(&get_env("SLA_WARNING_THRESHOLDS").unwrap_or_else(|_| "80".to_string()))?,
+            sla_warning_webhook: get_env_parse("SLA_WARNING_WEBHOOK").unwrap_or(false),
+            scheduler_backoff: get_env_duration("SCHEDULER_BACKOFF").unwrap_or(Duration::from_secs(30)),
+            scheduler_lease: non_zero("SCHEDULER_LEASE", get_env_duration("SCHEDULER_LEASE").unwrap_or(Duration::from_secs(30)))?,
+            retention: get_env_duration("RETENTION").unwrap_or(Duration::from_secs(600)),
+            rollup_interval: get_env_duration("ROLLUP_INTERVAL").unwrap_or(Duration::from_secs(60)),
+            archive_dir: get_env("ARCHIVE_DIR").ok(),
+            archive_schedule: get_env("ARCHIVE_SCHEDULE").unwrap_or_else(|_| "0 */5 * * * *".to_string()),
+            cleaner_dry_run: get_env_parse("CLEANER_DRY_RUN").unwrap_or(false),
+            cooldowns: parse_cooldowns(&get_env("COOLDOWNS").unwrap_or_default())?,
+            grpc_port: get_env_parse("GRPC_PORT").unwrap_or(50051),
+            ws_heartbeat_timeout: non_zero("WS_HEARTBEAT_TIMEOUT", get_env_duration("WS_HEARTBEAT_TIMEOUT").unwrap_or(Duration::from_secs(30)))?,
+            webhook_max_attempts: non_zero_count("WEBHOOK_MAX_ATTEMPTS", get_env_parse("WEBHOOK_MAX_ATTEMPTS").unwrap_or(5))?,
+            webhook_backoff: get_env_duration("WEBHOOK_BACKOFF").unwrap_or(Duration::from_secs(1)),
+            shutdown_timeout: get_env_duration("SHUTDOWN_TIMEOUT").unwrap_or(Duration::from_secs(10)),
         };
 
         Ok(config)
     }
 }
+
+/// Periods fed to `tokio::time::interval`, which panics on zero.
+fn non_zero(name: &str, duration: Duration) -> Result<Duration> {
+    if duration.is_zero() {
+        return Err(format!("{name} must be greater than 0s").into());
+    }
+
+    Ok(duration)
+}
+
+/// Counts where zero would disable what they count, e.g. webhook deliveries.
+fn non_zero_count(name: &str, count: u32) -> Result<u32> {
+    if count == 0 {
+        return Err(format!("{name} must be greater than 0").into());
+    }
+
+    Ok(count)
+}
+
+fn parse_thresholds(value: &str) -> Result<Vec<u64>> {
+    let mut thresholds = value
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|t| match t.parse::<u64>() {
+            Ok(percent) if (1..100).contains(&percent) => Ok(percent),
+            _ => Err(format!("SLA_WARNING_THRESHOLDS must be percents between 1 and 99, got {t}").into()),
+        })
+        .collect::<Result<Vec<u64>>>()?;
+
+    thresholds.sort_unstable();
+    thresholds.dedup();
+
+    Ok(thresholds)
+}
+
+fn parse_cooldowns(value: &str) -> Result<HashMap<String, u64>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(|c| match c.split_once('=') {
+            Some((process, cooldown)) if process.contains('/') => match parse_duration(cooldown.trim()) {
+                Ok(cooldown) => Ok((process.trim().to_string(), cooldown)),
+                Err(_) => Err(format!("COOLDOWNS has an invalid duration for {process}: {cooldown}").into()),
+            },
+            _ => Err(format!("COOLDOWNS entries must be app/process=<n>s|m|h, got {c}").into()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_zero_webhook_attempts() {
+        assert_eq!(non_zero_count("WEBHOOK_MAX_ATTEMPTS", 3).unwrap(), 3);
+
+        let err = non_zero_count("WEBHOOK_MAX_ATTEMPTS", 0).unwrap_err();
+        assert!(format!("{err:?}").contains("WEBHOOK_MAX_ATTEMPTS must be greater than 0"));
+    }
+}
@@ -0,0 +1,30 @@
+use derive_more::From;
+use crate::{db, time};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, From)]
+pub enum Error {
+    InvalidName(String),
+    InvalidTotal(String),
+    NotFound(String),
+    /// A countdown with this name is still waiting.
+    Exists(String),
+    /// The barrier was released before this participant arrived.
+    Released(String),
+    TimedOut(String),
+
+    #[from]
+    DB(db::error::Error),
+
+    #[from]
+    Time(time::error::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
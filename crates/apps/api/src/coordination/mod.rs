@@ -0,0 +1,208 @@
+//! Barriers and countdown latches, stored next to the processes.
+//!
+//! A barrier releases its participants together once the last of them has
+//! arrived, a latch releases its waiters once counted down to zero. Both give
+//! up at their timeout. Waiters poll the store, so a release is seen by the
+//! waiters of every replica.
+
+pub mod error;
+
+use std::time::Duration;
+
+use tokio::time::sleep;
+use tracing::instrument;
+
+use crate::db::countdowns::{count_down, create_countdown, get_countdown};
+use crate::db::Database;
+use crate::models::{is_valid_name, Countdown, CountdownKind, CountdownStatus, ResponseCountdown};
+use crate::time::from_epoch;
+
+use self::error::{Error, Result};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Creates a barrier for `total` participants or a latch counted down `total`
+/// times, which times out `timeout` seconds from now.
+///
+/// A released or timed out countdown with the same name is replaced.
+#[instrument(skip(db))]
+pub async fn create(db: &Database, kind: CountdownKind, name: &str, total: u32, timeout: u64) -> Result<ResponseCountdown> {
+    validate_name(name)?;
+    if total == 0 {
+        return Err(Error::InvalidTotal(format!("{kind} {name} needs at least one count")));
+    }
+
+    let now = from_epoch()?;
+    let countdown = Countdown {
+        name: name.to_string(),
+        total,
+        remaining: total,
+        create_at: now,
+        timeout_at: now + timeout,
+        updated_at: now,
+    };
+
+    if !create_countdown(db, kind, countdown.clone(), now).await? {
+        return Err(Error::Exists(format!("{kind} {name} is still waiting")));
+    }
+
+    Ok(countdown.to_response(now))
+}
+
+#[instrument(skip(db))]
+pub async fn get(db: &Database, kind: CountdownKind, name: &str) -> Result<ResponseCountdown> {
+    let now = from_epoch()?;
+
+    Ok(find(db, kind, name).await?.to_response(now))
+}
+
+/// Registers a participant of the barrier and blocks until the last one has arrived.
+///
+/// An arrival counts even if the participant stops waiting.
+#[instrument(skip(db))]
+pub async fn arrive(db: &Database, name: &str) -> Result<ResponseCountdown> {
+    let kind = CountdownKind::Barrier;
+
+    let Some(barrier) = count_down(db, kind, name, from_epoch()?).await? else {
+        let barrier = find(db, kind, name).await?;
+        return Err(match barrier.status(from_epoch()?) {
+            CountdownStatus::TimedOut => timed_out(kind, &barrier),
+            _ => Error::Released(format!("{kind} {name} was already released")),
+        });
+    };
+
+    wait_for(db, kind, barrier).await
+}
+
+/// Counts the latch down by one, counting down a released latch does nothing.
+#[instrument(skip(db))]
+pub async fn count_down_latch(db: &Database, name: &str) -> Result<ResponseCountdown> {
+    let kind = CountdownKind::Latch;
+    let now = from_epoch()?;
+
+    let latch = match count_down(db, kind, name, now).await? {
+        Some(latch) => latch,
+        None => find(db, kind, name).await?,
+    };
+
+    if latch.status(now) == CountdownStatus::TimedOut {
+        return Err(timed_out(kind, &latch));
+    }
+
+    Ok(latch.to_response(now))
+}
+
+/// Blocks until the countdown is released, or fails once it timed out.
+#[instrument(skip(db))]
+pub async fn wait(db: &Database, kind: CountdownKind, name: &str) -> Result<ResponseCountdown> {
+    let countdown = find(db, kind, name).await?;
+
+    wait_for(db, kind, countdown).await
+}
+
+/// Polls `countdown` until it's released or timed out.
+///
+/// It may be replaced under the same name in between polls, which its
+/// `create_at` tells. It was released then, unless replaced after its timeout.
+async fn wait_for(db: &Database, kind: CountdownKind, mut countdown: Countdown) -> Result<ResponseCountdown> {
+    loop {
+        let now = from_epoch()?;
+        match countdown.status(now) {
+            CountdownStatus::Released => return Ok(countdown.to_response(now)),
+            CountdownStatus::TimedOut => return Err(timed_out(kind, &countdown)),
+            CountdownStatus::Waiting => sleep(POLL_INTERVAL).await,
+        }
+
+        let current = find(db, kind, &countdown.name).await?;
+        if current.create_at == countdown.create_at {
+            countdown = current;
+        } else if current.create_at <= countdown.timeout_at {
+            countdown.remaining = 0;
+        } else {
+            return Err(timed_out(kind, &countdown));
+        }
+    }
+}
+
+async fn find(db: &Database, kind: CountdownKind, name: &str) -> Result<Countdown> {
+    validate_name(name)?;
+
+    get_countdown(db, kind, name)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("{kind} {name} not found")))
+}
+
+fn timed_out(kind: CountdownKind, countdown: &Countdown) -> Error {
+    Error::TimedOut(format!(
+        "{kind} {} timed out with {} of {} left",
+        countdown.name, countdown.remaining, countdown.total
+    ))
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if !is_valid_name(name) {
+        return Err(Error::InvalidName(format!("Invalid name {name}")));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_barrier_releases_every_participant_together() {
+        let db = Database::in_memory();
+        create(&db, CountdownKind::Barrier, "shards", 3, 60).await.unwrap();
+
+        let participants: Vec<_> = (0..2)
+            .map(|_| {
+                let db = db.clone();
+                tokio::spawn(async move { arrive(&db, "shards").await })
+            })
+            .collect();
+
+        sleep(POLL_INTERVAL * 2).await;
+        assert!(participants.iter().all(|p| !p.is_finished()));
+        let res = create(&db, CountdownKind::Barrier, "shards", 3, 60).await;
+        assert!(matches!(res, Err(Error::Exists(_))));
+
+        let last = arrive(&db, "shards").await.unwrap();
+        assert_eq!(last.status, CountdownStatus::Released);
+        for p in participants {
+            assert_eq!(p.await.unwrap().unwrap().remaining, 0);
+        }
+
+        assert!(matches!(arrive(&db, "shards").await, Err(Error::Released(_))));
+        assert!(matches!(arrive(&db, "other").await, Err(Error::NotFound(_))));
+
+        // Nobody else arrives: the latch waiter gives up at the timeout.
+        create(&db, CountdownKind::Latch, "import", 2, 1).await.unwrap();
+        assert_eq!(count_down_latch(&db, "import").await.unwrap().remaining, 1);
+        let res = wait(&db, CountdownKind::Latch, "import").await;
+        assert!(matches!(res, Err(Error::TimedOut(_))));
+    }
+
+    #[tokio::test]
+    async fn test_barrier_waiters_ignore_a_new_barrier_of_the_same_name() {
+        let db = Database::in_memory();
+        create(&db, CountdownKind::Barrier, "shards", 2, 60).await.unwrap();
+        let waiter = {
+            let db = db.clone();
+            tokio::spawn(async move { arrive(&db, "shards").await })
+        };
+
+        // Released and created again under the same name in a later second,
+        // before the waiter polls again.
+        let next_second = 1000 - SystemTime::now().duration_since(UNIX_EPOCH).unwrap().subsec_millis() as u64;
+        sleep(Duration::from_millis(next_second + 10)).await;
+        arrive(&db, "shards").await.unwrap();
+        create(&db, CountdownKind::Barrier, "shards", 3, 60).await.unwrap();
+
+        let released = tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap().unwrap();
+        assert_eq!((released.status, released.total), (CountdownStatus::Released, 2));
+    }
+}
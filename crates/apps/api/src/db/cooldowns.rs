@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+use tracing::{info, instrument};
+
+use crate::models::RunKind;
+use crate::time::from_epoch;
+
+use super::error::{Error, Result};
+use super::runs::ended_run;
+use super::Database;
+
+/// Cooldowns in seconds per `app/process`, for acquisitions that don't set one.
+#[derive(Debug, Clone, Default)]
+pub struct CooldownPolicy(HashMap<String, u64>);
+
+impl CooldownPolicy {
+    pub fn new(cooldowns: HashMap<String, u64>) -> Self {
+        CooldownPolicy(cooldowns)
+    }
+
+    pub fn get(&self, app: &str, process_name: &str) -> Option<u64> {
+        self.0.get(&format!("{app}/{process_name}")).copied()
+    }
+}
+
+/// Fails with `CoolingDown` while the last run of the process ended less than
+/// its cooldown ago.
+#[instrument(skip(db))]
+pub async fn check_cooldown(db: &Database, app: &str, process_name: &str) -> Result<()> {
+    let Some((run, ended_at)) = ended_run(db, RunKind::Cooldown, app, process_name).await? else {
+        return Ok(());
+    };
+
+    let retry_after = (ended_at + run.window).saturating_sub(from_epoch()?);
+    if retry_after == 0 {
+        return Ok(());
+    }
+
+    info!(event = "cooling down", app, process_name, retry_after);
+    Err(Error::CoolingDown { retry_after })
+}
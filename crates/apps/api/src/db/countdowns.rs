@@ -0,0 +1,108 @@
+use surrealdb::{Connection, Surreal};
+use tracing::instrument;
+
+use crate::models::{Countdown, CountdownKind};
+
+use super::error::Result;
+use super::{Backend, Database};
+
+// A countdown is replaced only once it's released or timed out. A missing
+// record has no `remaining`, so it's created.
+const REPLACEABLE: &str = "remaining = NONE OR remaining = 0 OR timeout_at < $now";
+
+/// Creates the countdown `countdown.name`, `false` if one with this name is still waiting.
+#[instrument(skip(db))]
+pub async fn create_countdown(db: &Database, kind: CountdownKind, countdown: Countdown, now: u64) -> Result<bool> {
+    match db.backend()? {
+        Backend::SurrealDB(conn) => create(&conn, kind, countdown, now).await,
+        Backend::Sqlite(store) => store.create_countdown(kind, countdown, now).await,
+    }
+}
+
+#[instrument(skip(db))]
+pub async fn get_countdown(db: &Database, kind: CountdownKind, name: &str) -> Result<Option<Countdown>> {
+    let conn = match db.backend()? {
+        Backend::SurrealDB(conn) => conn,
+        Backend::Sqlite(store) => return store.get_countdown(kind, name).await,
+    };
+
+    Ok(conn.select((kind.table(), name)).await?)
+}
+
+/// Takes one off `remaining` in a single statement, so concurrent arrivals
+/// are all counted. `None` if the countdown is missing, released or timed out.
+#[instrument(skip(db))]
+pub async fn count_down(db: &Database, kind: CountdownKind, name: &str, now: u64) -> Result<Option<Countdown>> {
+    match db.backend()? {
+        Backend::SurrealDB(conn) => decrement(&conn, kind, name, now).await,
+        Backend::Sqlite(store) => store.count_down(kind, name, now).await,
+    }
+}
+
+async fn create<C: Connection>(conn: &Surreal<C>, kind: CountdownKind, countdown: Countdown, now: u64) -> Result<bool> {
+    let mut response = conn
+        .query(format!("UPDATE type::thing($table, $name) CONTENT $countdown WHERE {REPLACEABLE}"))
+        .bind(("table", kind.table()))
+        .bind(("name", countdown.name.clone()))
+        .bind(("countdown", countdown))
+        .bind(("now", now))
+        .await?;
+
+    let created: Vec<Countdown> = response.take(0)?;
+    Ok(!created.is_empty())
+}
+
+async fn decrement<C: Connection>(conn: &Surreal<C>, kind: CountdownKind, name: &str, now: u64) -> Result<Option<Countdown>> {
+    let mut response = conn
+        .query(
+            // `UPDATE` alone would create a missing record.
+            "UPDATE (SELECT VALUE id FROM type::thing($table, $name)) SET remaining -= 1, updated_at = $now
+             WHERE remaining > 0 AND timeout_at >= $now",
+        )
+        .bind(("table", kind.table()))
+        .bind(("name", name))
+        .bind(("now", now))
+        .await?;
+
+    let updated: Vec<Countdown> = response.take(0)?;
+    Ok(updated.into_iter().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::surreal_in_memory;
+
+    #[tokio::test]
+    async fn test_surreal_counts_down_once_per_arrival() {
+        let conn = surreal_in_memory().await;
+
+        let now = 1_000_000;
+        let latch = |total| Countdown {
+            name: "shards".to_string(),
+            total,
+            remaining: total,
+            create_at: now,
+            timeout_at: now + 60,
+            updated_at: now,
+        };
+        let kind = CountdownKind::Latch;
+
+        assert!(decrement(&conn, kind, "shards", now).await.unwrap().is_none());
+        let missing: Option<Countdown> = conn.select(("latch", "shards")).await.unwrap();
+        assert!(missing.is_none());
+
+        assert!(create(&conn, kind, latch(2), now).await.unwrap());
+        assert!(!create(&conn, kind, latch(5), now).await.unwrap());
+
+        assert_eq!(decrement(&conn, kind, "shards", now).await.unwrap().unwrap().remaining, 1);
+        assert_eq!(decrement(&conn, kind, "shards", now).await.unwrap().unwrap().remaining, 0);
+        assert!(decrement(&conn, kind, "shards", now).await.unwrap().is_none());
+
+        // A timed out countdown doesn't count down, its name can be used again like a released one.
+        assert!(create(&conn, kind, latch(3), now).await.unwrap());
+        assert!(decrement(&conn, kind, "shards", now + 61).await.unwrap().is_none());
+        assert!(create(&conn, kind, latch(1), now + 61).await.unwrap());
+        assert!(decrement(&conn, CountdownKind::Barrier, "shards", now).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,26 @@
+use tracing::info;
+
+use crate::models::RunKind;
+use crate::time::from_epoch;
+
+use super::error::{Error, Result};
+use super::runs::ended_run;
+use super::Database;
+
+/// Fails with `AlreadyDone` while a run with `dedupe_key` completed less than
+/// its window ago, and with `ProcessExist` while one is still running.
+pub async fn check_not_done(db: &Database, app: &str, dedupe_key: &str) -> Result<()> {
+    let Some((run, completed_at)) = ended_run(db, RunKind::Dedupe, app, dedupe_key).await? else {
+        return Ok(());
+    };
+
+    if completed_at + run.window < from_epoch()? {
+        return Ok(());
+    }
+
+    info!(event = "already done", app, dedupe_key, process_id = %run.process_id);
+    Err(Error::AlreadyDone {
+        process_id: run.process_id,
+        completed_at,
+    })
+}
@@ -0,0 +1,32 @@
+use tracing::instrument;
+
+use crate::models::ElectionValue;
+
+use super::error::Result;
+use super::{Backend, Database};
+
+/// Replaces the value of the election `value.name`.
+#[instrument(skip(db))]
+pub async fn set_election_value(db: &Database, value: ElectionValue) -> Result<()> {
+    let conn = match db.backend()? {
+        Backend::SurrealDB(conn) => conn,
+        Backend::Sqlite(store) => return store.set_election_value(value).await,
+    };
+
+    let _: Option<ElectionValue> = conn
+        .update(("election", value.name.as_str()))
+        .content(value.clone())
+        .await?;
+
+    Ok(())
+}
+
+#[instrument(skip(db))]
+pub async fn get_election_value(db: &Database, name: &str) -> Result<Option<ElectionValue>> {
+    let conn = match db.backend()? {
+        Backend::SurrealDB(conn) => conn,
+        Backend::Sqlite(store) => return store.get_election_value(name).await,
+    };
+
+    Ok(conn.select(("election", name)).await?)
+}
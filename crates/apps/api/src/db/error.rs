@@ -6,14 +6,24 @@ use serde::Serialize;
 #[derive(Debug, Serialize, From)]
 pub enum Error {
     RecordNotFound,
+    ProcessExist,
+    InvalidStatus(String),
+    Reserved(String),
+    /// A run with this dedupe key completed within its window.
+    AlreadyDone { process_id: String, completed_at: u64 },
+    /// The last run ended less than its cooldown ago.
+    CoolingDown { retry_after: u64 },
+    StoreUnavailable,
     Repository(String),
     BadQuery,
+    Migration(String),
+    Sqlite(String),
 
     #[from]
     Time(time::error::Error),
 
-    #[from]
-    SurrealDB(surrealdb::Error),
+    // Boxed, the driver error would make every `Result` of this module large.
+    SurrealDB(Box<surrealdb::Error>),
 }
 
 impl std::fmt::Display for Error {
@@ -23,3 +33,15 @@ impl std::fmt::Display for Error {
 }
 
 impl std::error::Error for Error {}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::Sqlite(err.to_string())
+    }
+}
+
+impl From<surrealdb::Error> for Error {
+    fn from(err: surrealdb::Error) -> Self {
+        Self::SurrealDB(Box::new(err))
+    }
+}
@@ -0,0 +1,59 @@
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::models::Process;
+
+use super::error::Result;
+use super::repository::get_process_by_id;
+use super::Database;
+
+/// Current state of a process followed by every status change, ending once
+/// the process is finished or the receiver is dropped.
+pub async fn watch_process(db: &Database, id: &str) -> Result<ReceiverStream<Process>> {
+    // Subscribe before reading so a change in between is not lost.
+    let mut events = db.subscribe();
+    let first = get_process_by_id(db, id).await?;
+
+    let (tx, rx) = mpsc::channel(16);
+    let db = db.clone();
+    let id = id.to_string();
+
+    tokio::spawn(async move {
+        let mut last = (first.status.clone(), first.updated_at);
+        let mut finished = first.status.is_finished();
+
+        if tx.send(first).await.is_err() {
+            return;
+        }
+
+        while !finished {
+            let p = tokio::select! {
+                _ = tx.closed() => return,
+                event = events.recv() => match event {
+                    Ok(p) if p.process_id == id => p,
+                    Ok(_) => continue,
+                    // Too slow to keep up: fall back to the stored state.
+                    Err(RecvError::Lagged(_)) => match get_process_by_id(&db, &id).await {
+                        Ok(p) => p,
+                        Err(_) => return,
+                    },
+                    Err(RecvError::Closed) => return,
+                },
+            };
+
+            if (p.status.clone(), p.updated_at) == last {
+                continue;
+            }
+
+            last = (p.status.clone(), p.updated_at);
+            finished = p.status.is_finished();
+
+            if tx.send(p).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(ReceiverStream::new(rx))
+}
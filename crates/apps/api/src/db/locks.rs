@@ -0,0 +1,164 @@
+use tracing::instrument;
+
+use crate::models::{Run, RunKind};
+
+use super::cooldowns::{check_cooldown, CooldownPolicy};
+use super::dedupe::check_not_done;
+use super::error::{Error, Result};
+use super::repository::{create_process, delete_process_by_id, notify_subscribers};
+use super::runs::claim_run;
+use super::Database;
+
+/// A lock request, the same whatever API it came through.
+#[derive(Debug, Clone, Default)]
+pub struct Acquisition {
+    pub app: String,
+    pub process: String,
+    pub eta: u64,
+    /// Dedupe key and window in seconds.
+    pub dedupe: Option<(String, u64)>,
+    /// Seconds, `None` leaves it to the [`CooldownPolicy`].
+    pub cooldown: Option<u64>,
+}
+
+/// Takes the lock like `acquire_process`, unless the process is cooling down
+/// (`CoolingDown`) or a run with the dedupe key completed within its window
+/// (`AlreadyDone`). A dedupe key still held by a running process of the app
+/// fails with `ProcessExist`, like a held lock.
+///
+/// The dedupe key is checked again once the lock is held, a run that completed
+/// in between would be missed otherwise. The lock is only published once its
+/// runs are claimed, and deleted if they can't be.
+#[instrument(skip(db, cooldowns))]
+pub async fn acquire_lock(db: &Database, cooldowns: &CooldownPolicy, acquisition: Acquisition) -> Result<String> {
+    let Acquisition { app, process, eta, dedupe, cooldown } = acquisition;
+    let cooldown = cooldown.or_else(|| cooldowns.get(&app, &process)).filter(|c| *c > 0);
+
+    check_cooldown(db, &app, &process).await?;
+    if let Some((key, _)) = &dedupe {
+        check_not_done(db, &app, key).await?;
+    }
+
+    let id = create_process(db, app.clone(), process.clone(), eta).await?;
+
+    let claimed = claim_runs(db, &app, &process, &id, dedupe, cooldown).await;
+    if !matches!(claimed, Ok(true)) {
+        delete_process_by_id(db, &id).await?;
+        return Err(claimed.err().unwrap_or(Error::ProcessExist));
+    }
+
+    notify_subscribers(db, &id).await;
+
+    Ok(id)
+}
+
+/// Makes the lock `id` the latest run of its dedupe key and of its cooldown,
+/// `false` when a running process holds one of them.
+async fn claim_runs(
+    db: &Database,
+    app: &str,
+    process: &str,
+    id: &str,
+    dedupe: Option<(String, u64)>,
+    cooldown: Option<u64>,
+) -> Result<bool> {
+    let run = |key: String, window: u64| Run {
+        app: app.to_string(),
+        key,
+        process_id: id.to_string(),
+        window,
+        ended_at: 0,
+    };
+
+    if let Some((key, window)) = dedupe {
+        check_not_done(db, app, &key).await?;
+        if !claim_run(db, RunKind::Dedupe, run(key, window)).await? {
+            return Ok(false);
+        }
+    }
+
+    if let Some(cooldown) = cooldown {
+        if !claim_run(db, RunKind::Cooldown, run(process.to_string(), cooldown)).await? {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    use super::*;
+    use crate::db::repository::update_process_status;
+    use crate::db::runs::claim_run;
+    use crate::models::OperationStatus;
+
+    #[tokio::test]
+    async fn test_running_run_keeps_its_dedupe_key() {
+        for db in [Database::in_memory(), Database::surreal_in_memory().await] {
+            let acquire = |process: &str| {
+                let acquisition = Acquisition {
+                    app: "billing".to_string(),
+                    process: process.to_string(),
+                    eta: 60,
+                    dedupe: Some(("report:2026-10-18".to_string(), 86400)),
+                    cooldown: None,
+                };
+                let db = db.clone();
+                async move { acquire_lock(&db, &CooldownPolicy::default(), acquisition).await }
+            };
+
+            let first = acquire("invoices").await.unwrap();
+            assert!(matches!(acquire("reports").await, Err(Error::ProcessExist)));
+
+            let run = Run {
+                app: "billing".to_string(),
+                key: "report:2026-10-18".to_string(),
+                process_id: "reports".to_string(),
+                window: 86400,
+                ended_at: 0,
+            };
+            assert!(!claim_run(&db, RunKind::Dedupe, run).await.unwrap());
+
+            // Canceled, it no longer holds the key.
+            update_process_status(&db, &first, OperationStatus::Canceled).await.unwrap();
+            acquire("reports").await.unwrap();
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_lost_claims_publish_nothing() {
+        for db in [Database::in_memory(), Database::surreal_in_memory().await] {
+            let mut events = db.subscribe();
+
+            // Different processes, so they all get past the held lock and race for the key.
+            let racers: Vec<_> = (0..16)
+                .map(|i| {
+                    let acquisition = Acquisition {
+                        app: "billing".to_string(),
+                        process: format!("report-{i}"),
+                        eta: 60,
+                        dedupe: Some(("report:2026-10-19".to_string(), 86400)),
+                        cooldown: None,
+                    };
+                    let db = db.clone();
+                    tokio::spawn(async move { acquire_lock(&db, &CooldownPolicy::default(), acquisition).await })
+                })
+                .collect();
+
+            let mut won = Vec::new();
+            for racer in racers {
+                if let Ok(id) = racer.await.unwrap() {
+                    won.push(id);
+                }
+            }
+            assert_eq!(won.len(), 1);
+
+            let published = events.try_recv().unwrap();
+            assert_eq!((published.process_id.as_ref(), published.status), (won[0].as_str(), OperationStatus::New));
+            assert!(matches!(events.try_recv(), Err(TryRecvError::Empty)));
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use serde::Deserialize;
+use tracing::{info, instrument};
+
+use crate::time::from_epoch;
+
+use super::error::{Error, Result};
+use super::{Backend, Database};
+
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub statements: &'static str,
+}
+
+/// Ordered list of schema changes. Never edit an applied entry, append a new one.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "define_process_table",
+        statements: "
+            DEFINE TABLE process SCHEMAFULL;
+            DEFINE FIELD process_id ON TABLE process TYPE string;
+            DEFINE FIELD app ON TABLE process TYPE string;
+            DEFINE FIELD process_name ON TABLE process TYPE string;
+            DEFINE FIELD status ON TABLE process TYPE string
+                ASSERT $value INSIDE ['New', 'InProgress', 'Completed', 'Canceled', 'Outdated'];
+            DEFINE FIELD create_at ON TABLE process TYPE int;
+            DEFINE FIELD updated_at ON TABLE process TYPE int;
+            DEFINE FIELD ended_at ON TABLE process TYPE int DEFAULT 0;
+            DEFINE FIELD sla ON TABLE process TYPE int;
+        ",
+    },
+    Migration {
+        version: 2,
+        name: "index_process_lookups",
+        statements: "
+            DEFINE INDEX process_app ON TABLE process COLUMNS app;
+            DEFINE INDEX process_process_name ON TABLE process COLUMNS process_name;
+            DEFINE INDEX process_status ON TABLE process COLUMNS status;
+            DEFINE INDEX process_app_name_status ON TABLE process COLUMNS app, process_name, status;
+        ",
+    },
+    Migration {
+        version: 3,
+        name: "define_webhook_tables",
+        statements: "
+            DEFINE TABLE webhook SCHEMAFULL;
+            DEFINE FIELD webhook_id ON TABLE webhook TYPE string;
+            DEFINE FIELD url ON TABLE webhook TYPE string;
+            DEFINE FIELD secret ON TABLE webhook TYPE string;
+            DEFINE FIELD app ON TABLE webhook TYPE option<string>;
+            DEFINE FIELD process_name ON TABLE webhook TYPE option<string>;
+            DEFINE FIELD events ON TABLE webhook TYPE array<string>;
+            DEFINE FIELD create_at ON TABLE webhook TYPE int;
+
+            DEFINE TABLE webhook_dead_letter SCHEMAFULL;
+            DEFINE FIELD delivery_id ON TABLE webhook_dead_letter TYPE string;
+            DEFINE FIELD webhook_id ON TABLE webhook_dead_letter TYPE string;
+            DEFINE FIELD url ON TABLE webhook_dead_letter TYPE string;
+            DEFINE FIELD event ON TABLE webhook_dead_letter TYPE string;
+            DEFINE FIELD payload ON TABLE webhook_dead_letter TYPE string;
+            DEFINE FIELD attempts ON TABLE webhook_dead_letter TYPE int;
+            DEFINE FIELD last_error ON TABLE webhook_dead_letter TYPE string;
+            DEFINE FIELD create_at ON TABLE webhook_dead_letter TYPE int;
+            DEFINE INDEX webhook_dead_letter_webhook ON TABLE webhook_dead_letter COLUMNS webhook_id;
+        ",
+    },
+    Migration {
+        version: 4,
+        name: "index_process_updated_at",
+        statements: "
+            DEFINE INDEX process_updated_at ON TABLE process COLUMNS updated_at;
+        ",
+    },
+    Migration {
+        version: 5,
+        name: "define_election_table",
+        statements: "
+            DEFINE TABLE election SCHEMAFULL;
+            DEFINE FIELD name ON TABLE election TYPE string;
+            DEFINE FIELD lock_id ON TABLE election TYPE string;
+            DEFINE FIELD value ON TABLE election TYPE string;
+            DEFINE FIELD updated_at ON TABLE election TYPE int;
+        ",
+    },
+    Migration {
+        version: 6,
+        name: "define_barrier_table",
+        statements: "
+            DEFINE TABLE barrier SCHEMAFULL;
+            DEFINE FIELD name ON TABLE barrier TYPE string;
+            DEFINE FIELD total ON TABLE barrier TYPE int;
+            DEFINE FIELD remaining ON TABLE barrier TYPE int;
+            DEFINE FIELD create_at ON TABLE barrier TYPE int;
+            DEFINE FIELD timeout_at ON TABLE barrier TYPE int;
+            DEFINE FIELD updated_at ON TABLE barrier TYPE int;
+        ",
+    },
+    Migration {
+        version: 7,
+        name: "define_latch_table",
+        statements: "
+            DEFINE TABLE latch SCHEMAFULL;
+            DEFINE FIELD name ON TABLE latch TYPE string;
+            DEFINE FIELD total ON TABLE latch TYPE int;
+            DEFINE FIELD remaining ON TABLE latch TYPE int;
+            DEFINE FIELD create_at ON TABLE latch TYPE int;
+            DEFINE FIELD timeout_at ON TABLE latch TYPE int;
+            DEFINE FIELD updated_at ON TABLE latch TYPE int;
+        ",
+    },
+    Migration {
+        version: 8,
+        name: "define_dedupe_table",
+        statements: "
+            DEFINE TABLE dedupe SCHEMAFULL;
+            DEFINE FIELD app ON TABLE dedupe TYPE string;
+            DEFINE FIELD dedupe_key ON TABLE dedupe TYPE string;
+            DEFINE FIELD process_id ON TABLE dedupe TYPE string;
+            DEFINE FIELD window ON TABLE dedupe TYPE int;
+            DEFINE FIELD completed_at ON TABLE dedupe TYPE int DEFAULT 0;
+            DEFINE INDEX dedupe_process_id ON TABLE dedupe COLUMNS process_id;
+        ",
+    },
+    Migration {
+        version: 9,
+        name: "define_cooldown_table",
+        statements: "
+            DEFINE TABLE cooldown SCHEMAFULL;
+            DEFINE FIELD app ON TABLE cooldown TYPE string;
+            DEFINE FIELD process_name ON TABLE cooldown TYPE string;
+            DEFINE FIELD process_id ON TABLE cooldown TYPE string;
+            DEFINE FIELD cooldown ON TABLE cooldown TYPE int;
+            DEFINE FIELD ended_at ON TABLE cooldown TYPE int DEFAULT 0;
+            DEFINE INDEX cooldown_process_id ON TABLE cooldown COLUMNS process_id;
+        ",
+    },
+    Migration {
+        version: 10,
+        name: "define_acquisition_table",
+        statements: "
+            DEFINE TABLE acquisition SCHEMAFULL;
+            DEFINE FIELD app ON TABLE acquisition TYPE string;
+            DEFINE FIELD process_name ON TABLE acquisition TYPE string;
+            DEFINE FIELD process_id ON TABLE acquisition TYPE string;
+        ",
+    },
+];
+
+const MIGRATIONS_TABLE: &str = "
+    DEFINE TABLE _migrations SCHEMAFULL;
+    DEFINE FIELD version ON TABLE _migrations TYPE int;
+    DEFINE FIELD name ON TABLE _migrations TYPE string;
+    DEFINE FIELD applied_at ON TABLE _migrations TYPE int;
+";
+
+#[derive(Debug, Deserialize)]
+struct AppliedMigration {
+    version: u32,
+}
+
+/// Applies every migration newer than the last one recorded in `_migrations`
+/// and returns how many ran. The SQLite store creates its schema on open.
+#[instrument(skip(db))]
+pub async fn migrate(db: &Database) -> Result<usize> {
+    let conn = match db.backend()? {
+        Backend::SurrealDB(conn) => conn,
+        Backend::Sqlite(_) => return Ok(0),
+    };
+
+    conn.query(MIGRATIONS_TABLE).await?.check()?;
+
+    let mut response = conn.query("SELECT version FROM _migrations").await?;
+    let applied: Vec<AppliedMigration> = response.take(0)?;
+    let current = applied.iter().map(|m| m.version).max().unwrap_or(0);
+
+    let mut count = 0;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let query = format!(
+            "BEGIN TRANSACTION;
+            {}
+            CREATE type::thing('_migrations', $version) CONTENT {{
+                version: $version,
+                name: $name,
+                applied_at: $applied_at
+            }};
+            COMMIT TRANSACTION;",
+            migration.statements
+        );
+
+        conn.query(query)
+            .bind(("version", migration.version))
+            .bind(("name", migration.name))
+            .bind(("applied_at", from_epoch()?))
+            .await?
+            .check()
+            .map_err(|e| Error::Migration(format!("{} ({}): {}", migration.version, migration.name, e)))?;
+
+        info!(event = "migration_applied", version = migration.version, name = migration.name);
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migration_versions_are_increasing() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|m| m.version).collect();
+
+        assert!(versions.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(versions.first(), Some(&1));
+    }
+}
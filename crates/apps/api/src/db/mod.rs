@@ -1,35 +1,223 @@
+pub mod cooldowns;
+pub mod countdowns;
+pub mod dedupe;
+pub mod elections;
 pub mod error;
+pub mod events;
+pub mod locks;
+pub mod migrations;
 pub mod repository;
+pub mod runs;
+pub mod sqlite;
+pub mod webhooks;
+mod supervisor;
+#[cfg(test)]
+pub(crate) mod testing;
 
-use std::sync::Arc;
-use surrealdb::engine::remote::ws::{Client, Ws};
+use std::sync::{Arc, RwLock};
+use serde::Serialize;
+use surrealdb::engine::any::{self, Any};
 use surrealdb::opt::auth::Root;
 use surrealdb::Surreal;
+use tokio::sync::{broadcast, watch};
 
-use self::error::Result;
+use crate::config::{config, StorageBackend};
+use crate::models::Process;
+use crate::time::from_epoch;
+use self::error::{Error, Result};
+use self::sqlite::SqliteStore;
+
+pub use self::supervisor::supervise;
+
+/// Slow subscribers lagging further behind than this miss events.
+const EVENTS_CAPACITY: usize = 1024;
 
 #[derive(Clone, Debug)]
 pub struct Database {
-    pub conn: Arc<Surreal<Client>>,
+    store: Store,
+    health: Arc<watch::Sender<Health>>,
+    // Every created process and status change, published by the repository.
+    events: broadcast::Sender<Process>,
+    // Name of every election whose leader or value changed.
+    elections: broadcast::Sender<String>,
+}
+
+#[derive(Clone, Debug)]
+enum Store {
+    // The client is swapped out as a whole when the supervisor reconnects.
+    SurrealDB(Arc<RwLock<Arc<Surreal<Any>>>>),
+    Sqlite(Arc<SqliteStore>),
+}
+
+/// Borrowed view of the active store, handed out by [`Database::backend`].
+pub(crate) enum Backend<'a> {
+    SurrealDB(Arc<Surreal<Any>>),
+    Sqlite(&'a SqliteStore),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Reconnecting,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Health {
+    pub backend: &'static str,
+    pub state: ConnectionState,
+    pub since: u64,
 }
 
 pub async fn new() -> Result<Database> {
+    let (store, backend, state) = match config().storage {
+        StorageBackend::SurrealDB => (
+            Store::SurrealDB(Arc::new(RwLock::new(Arc::new(Surreal::init())))),
+            "surrealdb",
+            ConnectionState::Connecting,
+        ),
+        StorageBackend::Sqlite => (
+            Store::Sqlite(Arc::new(SqliteStore::open(&config().sqlite_path)?)),
+            "sqlite",
+            // SQLite file is opened (and its schema applied) right here.
+            ConnectionState::Connected,
+        ),
+    };
+
+    let (health, _) = watch::channel(Health {
+        backend,
+        state,
+        since: from_epoch()?,
+    });
+
     Ok(Database {
-        conn: Arc::new(Surreal::new::<Ws>("127.0.0.1:8000").await?),
+        store,
+        health: Arc::new(health),
+        events: broadcast::channel(EVENTS_CAPACITY).0,
+        elections: broadcast::channel(EVENTS_CAPACITY).0,
     })
 }
 
 impl Database {
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        let (health, _) = watch::channel(Health {
+            backend: "sqlite",
+            state: ConnectionState::Connected,
+            since: 0,
+        });
+
+        Database {
+            store: Store::Sqlite(Arc::new(SqliteStore::open_in_memory().unwrap())),
+            health: Arc::new(health),
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+            elections: broadcast::channel(EVENTS_CAPACITY).0,
+        }
+    }
+
+    /// [`Database::in_memory`] on an in-memory SurrealDB, migrated.
+    #[cfg(test)]
+    pub async fn surreal_in_memory() -> Self {
+        let (health, _) = watch::channel(Health {
+            backend: "surrealdb",
+            state: ConnectionState::Connected,
+            since: 0,
+        });
+
+        Database {
+            store: Store::SurrealDB(Arc::new(RwLock::new(Arc::new(testing::surreal_in_memory().await)))),
+            health: Arc::new(health),
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+            elections: broadcast::channel(EVENTS_CAPACITY).0,
+        }
+    }
+
     pub async fn connect(&self) -> Result<()> {
-        self.conn
-            .signin(Root {
-                username: "surreal_user",
-                password: "dev_surreal_pass",
-            })
-            .await?;
+        let slot = match &self.store {
+            Store::SurrealDB(slot) => slot,
+            Store::Sqlite(_) => return Ok(()),
+        };
 
-        self.conn.use_ns("flowlocker").use_db("processes").await?;
+        let conn = connect_surreal().await?;
+        *slot.write().map_err(|_| Error::Repository("connection slot is poisoned".to_string()))? =
+            Arc::new(conn);
+        self.set_state(ConnectionState::Connected);
 
         Ok(())
     }
+
+    pub fn health(&self) -> Health {
+        self.health.borrow().clone()
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.health.borrow().state == ConnectionState::Connected
+    }
+
+    /// Returns the active store, or `StoreUnavailable` while the supervisor
+    /// is (re)connecting so callers fail fast instead of waiting on a dead socket.
+    pub(crate) fn backend(&self) -> Result<Backend<'_>> {
+        match &self.store {
+            Store::Sqlite(store) => Ok(Backend::Sqlite(store)),
+            Store::SurrealDB(slot) => {
+                if !self.is_available() {
+                    return Err(Error::StoreUnavailable);
+                }
+
+                let conn = slot
+                    .read()
+                    .map_err(|_| Error::Repository("connection slot is poisoned".to_string()))?;
+
+                Ok(Backend::SurrealDB(Arc::clone(&conn)))
+            }
+        }
+    }
+
+    /// Receives the new state of every process that is created or changes status.
+    pub fn subscribe(&self) -> broadcast::Receiver<Process> {
+        self.events.subscribe()
+    }
+
+    pub(crate) fn has_subscribers(&self) -> bool {
+        self.events.receiver_count() > 0
+    }
+
+    pub(crate) fn publish(&self, process: Process) {
+        // No subscribers is not an error.
+        let _ = self.events.send(process);
+    }
+
+    /// Receives the name of every election that got a new leader or value.
+    pub fn subscribe_elections(&self) -> broadcast::Receiver<String> {
+        self.elections.subscribe()
+    }
+
+    pub(crate) fn publish_election(&self, name: &str) {
+        let _ = self.elections.send(name.to_string());
+    }
+
+    pub(crate) fn set_state(&self, state: ConnectionState) {
+        self.health.send_if_modified(|health| {
+            if health.state == state {
+                return false;
+            }
+            health.state = state;
+            health.since = from_epoch().unwrap_or_default();
+            true
+        });
+    }
+}
+
+async fn connect_surreal() -> Result<Surreal<Any>> {
+    let conn = any::connect("ws://127.0.0.1:8000").await?;
+
+    conn.signin(Root {
+        username: "surreal_user",
+        password: "dev_surreal_pass",
+    })
+    .await?;
+
+    conn.use_ns("flowlocker").use_db("processes").await?;
+
+    Ok(conn)
 }
@@ -0,0 +1,194 @@
+use surrealdb::{Connection, Surreal};
+use tracing::instrument;
+
+use crate::models::{OperationStatus, Run, RunKind};
+
+use super::error::{Error, Result};
+use super::repository::{get_process_by_id, statuses, UNFINISHED};
+use super::{Backend, Database};
+
+/// Makes `run` the latest of its key, unless the latest one is still running.
+/// Returns whether `run` got the key.
+#[instrument(skip(db))]
+pub async fn claim_run(db: &Database, kind: RunKind, run: Run) -> Result<bool> {
+    match db.backend()? {
+        Backend::SurrealDB(conn) => claim(&conn, kind, run).await,
+        Backend::Sqlite(store) => store.claim_run(kind, run).await,
+    }
+}
+
+/// The latest run of `key` and when it ended, `None` when there's none or it
+/// ended without opening the window. Fails with `ProcessExist` while it runs.
+pub async fn ended_run(db: &Database, kind: RunKind, app: &str, key: &str) -> Result<Option<(Run, u64)>> {
+    let Some(run) = get_run(db, kind, app, key).await? else {
+        return Ok(None);
+    };
+
+    // Ended but not marked yet: the process tells.
+    let ended_at = match run.ended_at {
+        0 => match get_process_by_id(db, &run.process_id).await {
+            Ok(p) if kind.ends(&p.status) => p.ended_at,
+            Ok(p) if !p.status.is_finished() => return Err(Error::ProcessExist),
+            Ok(_) | Err(Error::RecordNotFound) => return Ok(None),
+            Err(e) => return Err(e),
+        },
+        ended_at => ended_at,
+    };
+
+    Ok(Some((run, ended_at)))
+}
+
+/// Called when `process_id` changes to `status`: the runs it ends open their window.
+pub async fn end_runs(db: &Database, process_id: &str, status: &OperationStatus, now: u64) -> Result<()> {
+    for kind in [RunKind::Dedupe, RunKind::Cooldown] {
+        if !kind.ends(status) {
+            continue;
+        }
+
+        match db.backend()? {
+            Backend::SurrealDB(conn) => end(&conn, kind, process_id, now).await?,
+            Backend::Sqlite(store) => store.end_run(kind, process_id, now).await?,
+        }
+    }
+
+    Ok(())
+}
+
+async fn get_run(db: &Database, kind: RunKind, app: &str, key: &str) -> Result<Option<Run>> {
+    match db.backend()? {
+        Backend::SurrealDB(conn) => get(&conn, kind, app, key).await,
+        Backend::Sqlite(store) => store.get_run(kind, app, key).await,
+    }
+}
+
+// Two claims that both find the key free write the same record, so they
+// conflict on commit even when the store doesn't serialize them.
+async fn claim<C: Connection>(conn: &Surreal<C>, kind: RunKind, run: Run) -> Result<bool> {
+    let (key, window, ended) = kind.columns();
+
+    conn.query(format!(
+        "BEGIN TRANSACTION;
+         LET $held = (SELECT VALUE type::thing('process', process_id) FROM type::thing($table, [$app, $key]));
+         LET $live = (SELECT VALUE id FROM $held WHERE status INSIDE $unfinished);
+         IF array::len($live) = 0 {{
+             UPDATE type::thing($table, [$app, $key])
+                 CONTENT {{ app: $app, {key}: $key, process_id: $process_id, {window}: $window, {ended}: $ended_at }}
+         }};
+         COMMIT TRANSACTION;"
+    ))
+    .bind(("table", kind.table()))
+    .bind(("app", run.app.clone()))
+    .bind(("key", run.key.clone()))
+    .bind(("process_id", run.process_id.clone()))
+    .bind(("window", run.window))
+    .bind(("ended_at", run.ended_at))
+    .bind(("unfinished", statuses(&UNFINISHED)))
+    .await?
+    .check()?;
+
+    Ok(get(conn, kind, &run.app, &run.key).await?.is_some_and(|r| r.process_id == run.process_id))
+}
+
+async fn get<C: Connection>(conn: &Surreal<C>, kind: RunKind, app: &str, key: &str) -> Result<Option<Run>> {
+    let (key_field, window, ended) = kind.columns();
+
+    let mut response = conn
+        .query(format!(
+            "SELECT app, {key_field} AS key, process_id, {window} AS window, {ended} AS ended_at
+             FROM type::thing($table, [$app, $key])"
+        ))
+        .bind(("table", kind.table()))
+        .bind(("app", app))
+        .bind(("key", key))
+        .await?;
+
+    let runs: Vec<Run> = response.take(0)?;
+    Ok(runs.into_iter().next())
+}
+
+async fn end<C: Connection>(conn: &Surreal<C>, kind: RunKind, process_id: &str, now: u64) -> Result<()> {
+    let (_, _, ended) = kind.columns();
+
+    conn.query(format!(
+        "UPDATE (SELECT VALUE id FROM type::table($table) WHERE process_id = $process_id AND {ended} = 0)
+         SET {ended} = $now"
+    ))
+    .bind(("table", kind.table()))
+    .bind(("process_id", process_id))
+    .bind(("now", now))
+    .await?
+    .check()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::cooldowns::CooldownPolicy;
+    use crate::db::locks::{acquire_lock, Acquisition};
+    use crate::db::repository::{outdate_expired_processes, outdate_process_if_expired};
+    use crate::db::testing::surreal_in_memory;
+    use crate::time::from_epoch;
+
+    #[tokio::test]
+    async fn test_surreal_ends_the_latest_run_of_each_key() {
+        let conn = surreal_in_memory().await;
+
+        for kind in [RunKind::Dedupe, RunKind::Cooldown] {
+            let run = |process_id: &str| Run {
+                app: "crm".to_string(),
+                key: "sync".to_string(),
+                process_id: process_id.to_string(),
+                window: 600,
+                ended_at: 0,
+            };
+
+            assert!(get(&conn, kind, "crm", "sync").await.unwrap().is_none());
+            assert!(claim(&conn, kind, run("first")).await.unwrap());
+            end(&conn, kind, "first", 100).await.unwrap();
+            assert!(claim(&conn, kind, run("second")).await.unwrap());
+
+            end(&conn, kind, "first", 200).await.unwrap();
+            assert_eq!(get(&conn, kind, "crm", "sync").await.unwrap().unwrap().ended_at, 0);
+
+            end(&conn, kind, "second", 300).await.unwrap();
+            end(&conn, kind, "second", 400).await.unwrap();
+            let ended = get(&conn, kind, "crm", "sync").await.unwrap().unwrap();
+            assert_eq!((ended.process_id.as_str(), ended.window, ended.ended_at), ("second", 600, 300));
+
+            assert!(get(&conn, kind, "other", "sync").await.unwrap().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_outdated_runs_start_their_cooldown() {
+        for db in [Database::in_memory(), Database::surreal_in_memory().await] {
+            let acquire = |process: &str| {
+                let acquisition = Acquisition {
+                    app: "crm".to_string(),
+                    process: process.to_string(),
+                    eta: 1,
+                    cooldown: Some(600),
+                    ..Default::default()
+                };
+                let db = db.clone();
+                async move { acquire_lock(&db, &CooldownPolicy::default(), acquisition).await.unwrap() }
+            };
+            let ended_at = |process: &str| {
+                let db = db.clone();
+                let process = process.to_string();
+                async move { get_run(&db, RunKind::Cooldown, "crm", &process).await.unwrap().unwrap().ended_at }
+            };
+
+            acquire("sync").await;
+            let import = acquire("import").await;
+            let later = from_epoch().unwrap() + 10;
+
+            assert!(outdate_process_if_expired(&db, &import, later).await.unwrap().is_some());
+            assert_eq!(outdate_expired_processes(&db, later).await.unwrap().len(), 1);
+            assert_eq!(ended_at("import").await, later);
+            assert_eq!(ended_at("sync").await, later);
+        }
+    }
+}
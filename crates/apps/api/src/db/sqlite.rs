@@ -0,0 +1,948 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::types::Type;
+use rusqlite::{named_params, Connection, OptionalExtension, Row, ToSql, TransactionBehavior};
+use tracing::{debug, instrument};
+use uuid::Uuid;
+
+use crate::models::{Countdown, CountdownKind, DeadLetter, ElectionValue, OperationStatus, Process, Run, RunKind, Webhook, WebhookEvent};
+use lib_query_builder::builder::Order;
+use crate::time::from_epoch;
+
+use super::error::{Error, Result};
+use super::repository::{ProcessCount, ProcessFilter, SortField};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS process (
+    process_id   TEXT PRIMARY KEY NOT NULL,
+    app          TEXT NOT NULL,
+    process_name TEXT NOT NULL,
+    status       TEXT NOT NULL,
+    create_at    INTEGER NOT NULL,
+    updated_at   INTEGER NOT NULL,
+    ended_at     INTEGER NOT NULL DEFAULT 0,
+    sla          INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS process_app_name_status ON process (app, process_name, status);
+CREATE INDEX IF NOT EXISTS process_status_updated_at ON process (status, updated_at);
+
+CREATE TABLE IF NOT EXISTS webhook (
+    webhook_id   TEXT PRIMARY KEY NOT NULL,
+    url          TEXT NOT NULL,
+    secret       TEXT NOT NULL,
+    app          TEXT,
+    process_name TEXT,
+    events       TEXT NOT NULL,
+    create_at    INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS webhook_dead_letter (
+    delivery_id TEXT PRIMARY KEY NOT NULL,
+    webhook_id  TEXT NOT NULL,
+    url         TEXT NOT NULL,
+    event       TEXT NOT NULL,
+    payload     TEXT NOT NULL,
+    attempts    INTEGER NOT NULL,
+    last_error  TEXT NOT NULL,
+    create_at   INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS webhook_dead_letter_webhook ON webhook_dead_letter (webhook_id);
+
+CREATE TABLE IF NOT EXISTS election (
+    name       TEXT PRIMARY KEY NOT NULL,
+    lock_id    TEXT NOT NULL,
+    value      TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS barrier (
+    name       TEXT PRIMARY KEY NOT NULL,
+    total      INTEGER NOT NULL,
+    remaining  INTEGER NOT NULL,
+    create_at  INTEGER NOT NULL,
+    timeout_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS latch (
+    name       TEXT PRIMARY KEY NOT NULL,
+    total      INTEGER NOT NULL,
+    remaining  INTEGER NOT NULL,
+    create_at  INTEGER NOT NULL,
+    timeout_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS dedupe (
+    app          TEXT NOT NULL,
+    dedupe_key   TEXT NOT NULL,
+    process_id   TEXT NOT NULL,
+    window       INTEGER NOT NULL,
+    completed_at INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (app, dedupe_key)
+);
+CREATE INDEX IF NOT EXISTS dedupe_process_id ON dedupe (process_id);
+
+CREATE TABLE IF NOT EXISTS cooldown (
+    app          TEXT NOT NULL,
+    process_name TEXT NOT NULL,
+    process_id   TEXT NOT NULL,
+    cooldown     INTEGER NOT NULL,
+    ended_at     INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (app, process_name)
+);
+CREATE INDEX IF NOT EXISTS cooldown_process_id ON cooldown (process_id);
+";
+
+/// Unfinished processes past their SLA at `:now`, see the repository's `EXPIRED`.
+const EXPIRED: &str = "status IN ('New', 'InProgress') AND create_at + sla < :now";
+/// Finished processes last updated before `:before`.
+const RETIRED: &str = "status IN ('Completed', 'Canceled', 'Outdated') AND updated_at < :before";
+
+/// Single-file SQLite store for deployments that can't run SurrealDB.
+///
+/// rusqlite is blocking, so every call is moved to the blocking pool and
+/// serialized through one connection.
+#[derive(Debug, Clone)]
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &str) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+
+        Self::init(conn)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    /// Bulk load for tests and benchmarks, rows are written as given.
+    #[cfg(test)]
+    pub async fn insert_processes(&self, processes: Vec<Process>) -> Result<()> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT INTO process (process_id, app, process_name, status, create_at, updated_at, ended_at, sla)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                )?;
+                for p in &processes {
+                    stmt.execute(rusqlite::params![
+                        p.process_id.as_ref(),
+                        p.app.as_ref(),
+                        p.process_name.as_ref(),
+                        p.status.to_string(),
+                        p.create_at,
+                        p.updated_at,
+                        p.ended_at,
+                        p.sla,
+                    ])?;
+                }
+            }
+            tx.commit()?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(SqliteStore {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|_| Error::Repository("SQLite connection is poisoned".to_string()))?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| Error::Repository(e.to_string()))?
+    }
+
+    /// Checks for a running process and creates a new one inside a single
+    /// `BEGIN IMMEDIATE` transaction, so two acquirers can't both succeed.
+    #[instrument(skip(self))]
+    pub async fn acquire_process(&self, app_name: String, process: String, eta: u64) -> Result<String> {
+        let now_time = from_epoch()?;
+
+        self.with_conn(move |conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+            if !select_running(&tx, &app_name, &process)?.is_empty() {
+                return Err(Error::ProcessExist);
+            }
+
+            let id = insert_process(&tx, &app_name, &process, eta, now_time)?;
+            tx.commit()?;
+
+            Ok(id)
+        })
+        .await
+    }
+
+    pub async fn update_process_status(&self, id: &str, status: OperationStatus) -> Result<()> {
+        let id = id.to_string();
+        let now_time = from_epoch()?;
+
+        self.with_conn(move |conn| {
+            match status {
+                OperationStatus::Completed | OperationStatus::Canceled | OperationStatus::Outdated => {
+                    conn.execute(
+                        "UPDATE process SET status = :status, updated_at = :now, ended_at = :now WHERE process_id = :id",
+                        named_params! { ":status": status.to_string(), ":now": now_time, ":id": id },
+                    )?;
+                }
+                _ => {
+                    conn.execute(
+                        "UPDATE process SET status = :status, updated_at = :now WHERE process_id = :id",
+                        named_params! { ":status": status.to_string(), ":now": now_time, ":id": id },
+                    )?;
+                }
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn delete_process_by_id(&self, id: &str) -> Result<()> {
+        let id = id.to_string();
+
+        self.with_conn(move |conn| {
+            conn.execute(
+                "DELETE FROM process WHERE process_id = :id",
+                named_params! { ":id": id },
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn renew_process(&self, id: &str, sla: u64) -> Result<()> {
+        let id = id.to_string();
+
+        self.with_conn(move |conn| {
+            conn.execute(
+                "UPDATE process SET sla = :sla WHERE process_id = :id",
+                named_params! { ":sla": sla, ":id": id },
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn get_process_by_id(&self, id: &str) -> Result<Process> {
+        let id = id.to_string();
+
+        self.with_conn(move |conn| {
+            conn.query_row(
+                "SELECT * FROM process WHERE process_id = :id",
+                named_params! { ":id": id },
+                process_from_row,
+            )
+            .optional()?
+            .ok_or(Error::RecordNotFound)
+        })
+        .await
+    }
+
+    pub async fn get_unfinished_processes(&self) -> Result<Vec<Process>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT * FROM process WHERE status IN ('New', 'InProgress')")?;
+            let rows = stmt.query_map([], process_from_row)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<Process>>>()?)
+        })
+        .await
+    }
+
+    pub async fn get_expired_processes(&self, now: u64) -> Result<Vec<Process>> {
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(&format!("SELECT * FROM process WHERE {EXPIRED}"))?;
+            let rows = stmt.query_map(named_params! { ":now": now }, process_from_row)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<Process>>>()?)
+        })
+        .await
+    }
+
+    pub async fn outdate_expired_processes(&self, now: u64) -> Result<Vec<Process>> {
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(&format!(
+                "UPDATE process SET status = :outdated, updated_at = :now, ended_at = :now WHERE {EXPIRED} RETURNING *"
+            ))?;
+            let rows = stmt.query_map(
+                named_params! { ":outdated": OperationStatus::Outdated.to_string(), ":now": now },
+                process_from_row,
+            )?;
+            Ok(rows.collect::<rusqlite::Result<Vec<Process>>>()?)
+        })
+        .await
+    }
+
+    pub async fn outdate_process_if_expired(&self, id: &str, now: u64) -> Result<Option<Process>> {
+        let id = id.to_string();
+
+        self.with_conn(move |conn| {
+            Ok(conn
+                .query_row(
+                    &format!(
+                        "UPDATE process SET status = :outdated, updated_at = :now, ended_at = :now
+                         WHERE process_id = :id AND {EXPIRED} RETURNING *"
+                    ),
+                    named_params! { ":outdated": OperationStatus::Outdated.to_string(), ":now": now, ":id": id },
+                    process_from_row,
+                )
+                .optional()?)
+        })
+        .await
+    }
+
+    pub async fn get_retired_processes(&self, before: u64) -> Result<Vec<Process>> {
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(&format!("SELECT * FROM process WHERE {RETIRED}"))?;
+            let rows = stmt.query_map(named_params! { ":before": before }, process_from_row)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<Process>>>()?)
+        })
+        .await
+    }
+
+    pub async fn delete_retired_processes(&self, before: u64) -> Result<Vec<Process>> {
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(&format!("DELETE FROM process WHERE {RETIRED} RETURNING *"))?;
+            let rows = stmt.query_map(named_params! { ":before": before }, process_from_row)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<Process>>>()?)
+        })
+        .await
+    }
+
+    pub async fn get_finished_processes(&self, until: u64, after_at: u64, after_id: String, limit: u32) -> Result<Vec<Process>> {
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT * FROM process
+                 WHERE status IN ('Completed', 'Canceled', 'Outdated') AND updated_at >= :after_at AND updated_at < :until
+                     AND (updated_at > :after_at OR process_id > :after_id)
+                 ORDER BY updated_at, process_id LIMIT :limit",
+            )?;
+            let rows = stmt.query_map(
+                named_params! { ":after_at": after_at, ":after_id": after_id, ":until": until, ":limit": limit },
+                process_from_row,
+            )?;
+            Ok(rows.collect::<rusqlite::Result<Vec<Process>>>()?)
+        })
+        .await
+    }
+
+    pub async fn count_processes(&self) -> Result<Vec<ProcessCount>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT app, status, COUNT(*) AS count FROM process GROUP BY app, status")?;
+            let rows = stmt.query_map([], |row| {
+                let status: String = row.get("status")?;
+                Ok(ProcessCount {
+                    app: row.get("app")?,
+                    status: status
+                        .parse::<OperationStatus>()
+                        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, Type::Text, e.into()))?,
+                    count: row.get("count")?,
+                })
+            })?;
+            Ok(rows.collect::<rusqlite::Result<Vec<ProcessCount>>>()?)
+        })
+        .await
+    }
+
+    /// Returns up to `filter.limit + 1` rows, paging is done by the repository.
+    pub async fn get_processes(&self, filter: ProcessFilter) -> Result<Vec<Process>> {
+        self.with_conn(move |conn| {
+            let mut query = String::from("SELECT * FROM process WHERE 1 = 1");
+            let mut args: Vec<(String, Box<dyn ToSql>)> = Vec::new();
+
+            if let Some(app) = filter.app {
+                query.push_str(" AND app = :app");
+                args.push((":app".to_string(), Box::new(app)));
+            }
+
+            if let Some(process_name) = filter.process_name {
+                query.push_str(" AND process_name = :process_name");
+                args.push((":process_name".to_string(), Box::new(process_name)));
+            }
+
+            if !filter.statuses.is_empty() {
+                let keys: Vec<String> = (0..filter.statuses.len()).map(|i| format!(":status_{i}")).collect();
+                query.push_str(&format!(" AND status IN ({})", keys.join(", ")));
+                for (key, status) in keys.into_iter().zip(filter.statuses) {
+                    args.push((key, Box::new(status.to_string())));
+                }
+            }
+
+            if let Some(created_after) = filter.created_after {
+                query.push_str(" AND create_at > :created_after");
+                args.push((":created_after".to_string(), Box::new(created_after)));
+            }
+
+            if let Some(created_before) = filter.created_before {
+                query.push_str(" AND create_at < :created_before");
+                args.push((":created_before".to_string(), Box::new(created_before)));
+            }
+
+            let column = match filter.sort {
+                SortField::CreateAt => "create_at",
+                SortField::UpdatedAt => "updated_at",
+            };
+            let (cmp, order) = match filter.order {
+                Order::Asc => (">", "ASC"),
+                Order::Desc => ("<", "DESC"),
+            };
+
+            if let Some(cursor) = filter.cursor {
+                query.push_str(&format!(
+                    " AND ({column} {cmp} :seek_value OR ({column} = :seek_value AND process_id {cmp} :seek_id))"
+                ));
+                args.push((":seek_value".to_string(), Box::new(cursor.value)));
+                args.push((":seek_id".to_string(), Box::new(cursor.process_id)));
+            }
+
+            query.push_str(&format!(" ORDER BY {column} {order}, process_id {order} LIMIT :limit"));
+            args.push((":limit".to_string(), Box::new(filter.limit + 1)));
+
+            debug!("SQLite query: {}", query);
+
+            let params: Vec<(&str, &dyn ToSql)> = args.iter().map(|(k, v)| (k.as_str(), v.as_ref())).collect();
+
+            let mut stmt = conn.prepare(&query)?;
+            let rows = stmt.query_map(params.as_slice(), process_from_row)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<Process>>>()?)
+        })
+        .await
+    }
+
+    pub async fn check_running_processes(&self, app: &str, process_name: &str) -> Result<Option<Vec<Process>>> {
+        let app = app.to_string();
+        let process_name = process_name.to_string();
+
+        let processes = self
+            .with_conn(move |conn| select_running(conn, &app, &process_name))
+            .await?;
+
+        if processes.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(processes))
+    }
+
+    pub async fn create_webhook(&self, webhook: Webhook) -> Result<()> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO webhook (webhook_id, url, secret, app, process_name, events, create_at)
+                 VALUES (:id, :url, :secret, :app, :process_name, :events, :create_at)",
+                named_params! {
+                    ":id": webhook.webhook_id,
+                    ":url": webhook.url,
+                    ":secret": webhook.secret,
+                    ":app": webhook.app,
+                    ":process_name": webhook.process_name,
+                    ":events": webhook.events.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(","),
+                    ":create_at": webhook.create_at,
+                },
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn get_webhooks(&self) -> Result<Vec<Webhook>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT * FROM webhook ORDER BY create_at, webhook_id")?;
+            let rows = stmt.query_map([], webhook_from_row)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<Webhook>>>()?)
+        })
+        .await
+    }
+
+    pub async fn delete_webhook(&self, id: &str) -> Result<()> {
+        let id = id.to_string();
+
+        self.with_conn(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM webhook WHERE webhook_id = :id",
+                named_params! { ":id": id },
+            )?;
+
+            if deleted == 0 {
+                return Err(Error::RecordNotFound);
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn insert_dead_letter(&self, letter: DeadLetter) -> Result<()> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO webhook_dead_letter
+                 (delivery_id, webhook_id, url, event, payload, attempts, last_error, create_at)
+                 VALUES (:delivery_id, :webhook_id, :url, :event, :payload, :attempts, :last_error, :create_at)",
+                named_params! {
+                    ":delivery_id": letter.delivery_id,
+                    ":webhook_id": letter.webhook_id,
+                    ":url": letter.url,
+                    ":event": letter.event.to_string(),
+                    ":payload": letter.payload,
+                    ":attempts": letter.attempts,
+                    ":last_error": letter.last_error,
+                    ":create_at": letter.create_at,
+                },
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn get_dead_letters(&self) -> Result<Vec<DeadLetter>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT * FROM webhook_dead_letter ORDER BY create_at, delivery_id")?;
+            let rows = stmt.query_map([], dead_letter_from_row)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<DeadLetter>>>()?)
+        })
+        .await
+    }
+
+    pub async fn set_election_value(&self, value: ElectionValue) -> Result<()> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO election (name, lock_id, value, updated_at)
+                 VALUES (:name, :lock_id, :value, :updated_at)",
+                named_params! {
+                    ":name": value.name,
+                    ":lock_id": value.lock_id,
+                    ":value": value.value,
+                    ":updated_at": value.updated_at,
+                },
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn get_election_value(&self, name: &str) -> Result<Option<ElectionValue>> {
+        let name = name.to_string();
+
+        self.with_conn(move |conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT * FROM election WHERE name = :name",
+                    named_params! { ":name": name },
+                    |row| {
+                        Ok(ElectionValue {
+                            name: row.get("name")?,
+                            lock_id: row.get("lock_id")?,
+                            value: row.get("value")?,
+                            updated_at: row.get("updated_at")?,
+                        })
+                    },
+                )
+                .optional()?)
+        })
+        .await
+    }
+
+    /// `false` if a countdown with this name is still waiting.
+    pub async fn create_countdown(&self, kind: CountdownKind, countdown: Countdown, now: u64) -> Result<bool> {
+        self.with_conn(move |conn| {
+            let created = conn.execute(
+                &format!(
+                    "INSERT INTO {table} (name, total, remaining, create_at, timeout_at, updated_at)
+                     VALUES (:name, :total, :remaining, :create_at, :timeout_at, :updated_at)
+                     ON CONFLICT (name) DO UPDATE SET
+                         total = excluded.total, remaining = excluded.remaining, create_at = excluded.create_at,
+                         timeout_at = excluded.timeout_at, updated_at = excluded.updated_at
+                     WHERE {table}.remaining = 0 OR {table}.timeout_at < :now",
+                    table = kind.table()
+                ),
+                named_params! {
+                    ":name": countdown.name,
+                    ":total": countdown.total,
+                    ":remaining": countdown.remaining,
+                    ":create_at": countdown.create_at,
+                    ":timeout_at": countdown.timeout_at,
+                    ":updated_at": countdown.updated_at,
+                    ":now": now,
+                },
+            )?;
+            Ok(created == 1)
+        })
+        .await
+    }
+
+    pub async fn get_countdown(&self, kind: CountdownKind, name: &str) -> Result<Option<Countdown>> {
+        let name = name.to_string();
+
+        self.with_conn(move |conn| {
+            Ok(conn
+                .query_row(
+                    &format!("SELECT * FROM {} WHERE name = :name", kind.table()),
+                    named_params! { ":name": name },
+                    countdown_from_row,
+                )
+                .optional()?)
+        })
+        .await
+    }
+
+    pub async fn count_down(&self, kind: CountdownKind, name: &str, now: u64) -> Result<Option<Countdown>> {
+        let name = name.to_string();
+
+        self.with_conn(move |conn| {
+            Ok(conn
+                .query_row(
+                    &format!(
+                        "UPDATE {} SET remaining = remaining - 1, updated_at = :now
+                         WHERE name = :name AND remaining > 0 AND timeout_at >= :now
+                         RETURNING *",
+                        kind.table()
+                    ),
+                    named_params! { ":name": name, ":now": now },
+                    countdown_from_row,
+                )
+                .optional()?)
+        })
+        .await
+    }
+
+    /// Inserts `run`, or replaces the run of its key unless that one's process
+    /// is still running. Returns whether `run` got the key.
+    pub async fn claim_run(&self, kind: RunKind, run: Run) -> Result<bool> {
+        let (key, window, ended) = kind.columns();
+        let sql = format!(
+            "INSERT INTO {table} (app, {key}, process_id, {window}, {ended})
+             VALUES (:app, :key, :process_id, :window, :ended_at)
+             ON CONFLICT (app, {key}) DO UPDATE SET
+                 process_id = excluded.process_id, {window} = excluded.{window}, {ended} = excluded.{ended}
+             WHERE NOT EXISTS (
+                 SELECT 1 FROM process WHERE process_id = {table}.process_id AND status IN (:new, :in_progress)
+             )",
+            table = kind.table(),
+        );
+
+        self.with_conn(move |conn| {
+            let changed = conn.execute(
+                &sql,
+                named_params! {
+                    ":app": run.app,
+                    ":key": run.key,
+                    ":process_id": run.process_id,
+                    ":window": run.window,
+                    ":ended_at": run.ended_at,
+                    ":new": OperationStatus::New.to_string(),
+                    ":in_progress": OperationStatus::InProgress.to_string(),
+                },
+            )?;
+            Ok(changed == 1)
+        })
+        .await
+    }
+
+    pub async fn get_run(&self, kind: RunKind, app: &str, key: &str) -> Result<Option<Run>> {
+        let (key_column, window, ended) = kind.columns();
+        let sql = format!(
+            "SELECT app, {key_column}, process_id, {window}, {ended} FROM {table}
+             WHERE app = :app AND {key_column} = :key",
+            table = kind.table(),
+        );
+        let app = app.to_string();
+        let key = key.to_string();
+
+        self.with_conn(move |conn| {
+            Ok(conn
+                .query_row(&sql, named_params! { ":app": app, ":key": key }, |row| {
+                    Ok(Run {
+                        app: row.get(0)?,
+                        key: row.get(1)?,
+                        process_id: row.get(2)?,
+                        window: row.get(3)?,
+                        ended_at: row.get(4)?,
+                    })
+                })
+                .optional()?)
+        })
+        .await
+    }
+
+    pub async fn end_run(&self, kind: RunKind, process_id: &str, now: u64) -> Result<()> {
+        let (_, _, ended) = kind.columns();
+        let sql = format!(
+            "UPDATE {table} SET {ended} = :now WHERE process_id = :process_id AND {ended} = 0",
+            table = kind.table(),
+        );
+        let process_id = process_id.to_string();
+
+        self.with_conn(move |conn| {
+            conn.execute(&sql, named_params! { ":process_id": process_id, ":now": now })?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+fn insert_process(conn: &Connection, app_name: &str, process: &str, eta: u64, now_time: u64) -> Result<String> {
+    let new_process_id = Uuid::now_v7().to_string();
+
+    conn.execute(
+        "INSERT INTO process (process_id, app, process_name, status, create_at, updated_at, ended_at, sla)
+         VALUES (:id, :app, :process_name, :status, :now, :now, 0, :sla)",
+        named_params! {
+            ":id": new_process_id,
+            ":app": app_name,
+            ":process_name": process,
+            ":status": OperationStatus::New.to_string(),
+            ":now": now_time,
+            ":sla": eta,
+        },
+    )?;
+
+    Ok(new_process_id)
+}
+
+fn select_running(conn: &Connection, app: &str, process_name: &str) -> Result<Vec<Process>> {
+    let mut stmt = conn.prepare(
+        "SELECT * FROM process WHERE app = :app AND process_name = :process_name AND status = :status",
+    )?;
+
+    let rows = stmt.query_map(
+        named_params! {
+            ":app": app,
+            ":process_name": process_name,
+            ":status": OperationStatus::New.to_string(),
+        },
+        process_from_row,
+    )?;
+
+    Ok(rows.collect::<rusqlite::Result<Vec<Process>>>()?)
+}
+
+fn process_from_row(row: &Row) -> rusqlite::Result<Process> {
+    let status: String = row.get("status")?;
+    let status = status
+        .parse::<OperationStatus>()
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, Type::Text, e.into()))?;
+
+    Ok(Process {
+        process_id: row.get::<_, String>("process_id")?.into(),
+        app: row.get::<_, String>("app")?.into(),
+        process_name: row.get::<_, String>("process_name")?.into(),
+        status,
+        create_at: row.get("create_at")?,
+        updated_at: row.get("updated_at")?,
+        ended_at: row.get("ended_at")?,
+        sla: row.get("sla")?,
+    })
+}
+
+fn webhook_from_row(row: &Row) -> rusqlite::Result<Webhook> {
+    let events: String = row.get("events")?;
+    let events = events
+        .split(',')
+        .filter(|e| !e.is_empty())
+        .map(|e| e.parse::<WebhookEvent>())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(5, Type::Text, e.into()))?;
+
+    Ok(Webhook {
+        webhook_id: row.get("webhook_id")?,
+        url: row.get("url")?,
+        secret: row.get("secret")?,
+        app: row.get("app")?,
+        process_name: row.get("process_name")?,
+        events,
+        create_at: row.get("create_at")?,
+    })
+}
+
+fn dead_letter_from_row(row: &Row) -> rusqlite::Result<DeadLetter> {
+    let event: String = row.get("event")?;
+    let event = event
+        .parse::<WebhookEvent>()
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, Type::Text, e.into()))?;
+
+    Ok(DeadLetter {
+        delivery_id: row.get("delivery_id")?,
+        webhook_id: row.get("webhook_id")?,
+        url: row.get("url")?,
+        event,
+        payload: row.get("payload")?,
+        attempts: row.get("attempts")?,
+        last_error: row.get("last_error")?,
+        create_at: row.get("create_at")?,
+    })
+}
+
+fn countdown_from_row(row: &Row) -> rusqlite::Result<Countdown> {
+    Ok(Countdown {
+        name: row.get("name")?,
+        total: row.get("total")?,
+        remaining: row.get("remaining")?,
+        create_at: row.get("create_at")?,
+        timeout_at: row.get("timeout_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::db::repository::Cursor;
+    use crate::db::testing::fixture;
+
+    #[tokio::test]
+    async fn test_acquire_rejects_running_process() {
+        let store = SqliteStore::open_in_memory().unwrap();
+
+        let id = store
+            .acquire_process("app".to_string(), "job".to_string(), 60)
+            .await
+            .unwrap();
+
+        let second = store
+            .acquire_process("app".to_string(), "job".to_string(), 60)
+            .await;
+        assert!(matches!(second, Err(Error::ProcessExist)));
+
+        store
+            .update_process_status(&id, OperationStatus::Completed)
+            .await
+            .unwrap();
+
+        let p = store.get_process_by_id(&id).await.unwrap();
+        assert_eq!(p.status, OperationStatus::Completed);
+        assert_ne!(p.ended_at, 0);
+
+        assert!(store
+            .acquire_process("app".to_string(), "job".to_string(), 60)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_get_processes_pages_with_cursor() {
+        let store = SqliteStore::open_in_memory().unwrap();
+
+        for i in 0..5 {
+            store
+                .acquire_process("app".to_string(), format!("job-{i}"), 60)
+                .await
+                .unwrap();
+        }
+
+        let mut filter = ProcessFilter {
+            limit: 2,
+            order: Order::Desc,
+            ..Default::default()
+        };
+
+        let mut seen = Vec::new();
+        loop {
+            let rows = store.get_processes(filter.clone()).await.unwrap();
+            let more = rows.len() > filter.limit as usize;
+            let page: Vec<Process> = rows.into_iter().take(filter.limit as usize).collect();
+
+            let last = page.last().unwrap();
+            filter.cursor = Some(Cursor {
+                value: last.create_at,
+                process_id: last.process_id.to_string(),
+            });
+            seen.extend(page.into_iter().map(|p| p.process_name.to_string()));
+
+            if !more {
+                break;
+            }
+        }
+
+        assert_eq!(seen, vec!["job-4", "job-3", "job-2", "job-1", "job-0"]);
+    }
+
+    #[tokio::test]
+    async fn test_expires_and_retires_in_one_statement() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let now = from_epoch().unwrap();
+
+        let short = store.acquire_process("app".to_string(), "short".to_string(), 10).await.unwrap();
+        let long = store.acquire_process("app".to_string(), "long".to_string(), 1000).await.unwrap();
+        let done = store.acquire_process("app".to_string(), "done".to_string(), 10).await.unwrap();
+        store.update_process_status(&done, OperationStatus::Completed).await.unwrap();
+
+        let expired = store.get_expired_processes(now + 100).await.unwrap();
+        assert_eq!(expired.iter().map(|p| p.process_id.as_ref()).collect::<Vec<_>>(), vec![short.as_str()]);
+
+        let outdated = store.outdate_expired_processes(now + 100).await.unwrap();
+        assert_eq!(outdated.len(), 1);
+        assert_eq!(outdated[0].status, OperationStatus::Outdated);
+        assert_eq!(outdated[0].ended_at, now + 100);
+
+        // `done` finished now, `short` at now + 100.
+        assert_eq!(store.get_retired_processes(now + 50).await.unwrap().len(), 1);
+        assert_eq!(store.delete_retired_processes(now + 200).await.unwrap().len(), 2);
+
+        assert!(store.get_process_by_id(&short).await.is_err());
+        assert_eq!(store.get_process_by_id(&long).await.unwrap().status, OperationStatus::New);
+    }
+
+    /// SQLite half of `repository::tests::bench_cleaner_tick`.
+    #[tokio::test]
+    #[ignore]
+    async fn bench_cleaner_tick() {
+        const ROWS: usize = 100_000;
+        let now = from_epoch().unwrap();
+        let cutoff = now - 600;
+
+        let store = SqliteStore::open_in_memory().unwrap();
+        store.insert_processes(fixture(ROWS, now)).await.unwrap();
+        let started = Instant::now();
+        let processes = store
+            .with_conn(|conn| {
+                let mut stmt = conn.prepare("SELECT * FROM process")?;
+                let rows = stmt.query_map([], process_from_row)?;
+                Ok(rows.collect::<rusqlite::Result<Vec<Process>>>()?)
+            })
+            .await
+            .unwrap();
+        for p in processes {
+            if !p.status.is_finished() && now > p.create_at + p.sla {
+                store.update_process_status(&p.process_id, OperationStatus::Outdated).await.unwrap();
+            } else if p.status.is_finished() && p.updated_at < cutoff {
+                store.delete_process_by_id(&p.process_id).await.unwrap();
+            }
+        }
+        let scan = started.elapsed();
+
+        let store = SqliteStore::open_in_memory().unwrap();
+        store.insert_processes(fixture(ROWS, now)).await.unwrap();
+        let started = Instant::now();
+        assert_eq!(store.outdate_expired_processes(now).await.unwrap().len(), ROWS / 100);
+        assert_eq!(store.delete_retired_processes(cutoff).await.unwrap().len(), ROWS / 100);
+        let tick = started.elapsed();
+
+        println!("sqlite scan: {scan:?}, statements: {tick:?}");
+
+        // Several times the figure in the README, and never more than the scan.
+        assert!(tick < Duration::from_millis(500) && tick < scan);
+    }
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use tracing::{error, info, instrument, warn};
+
+use super::{ConnectionState, Database, Store};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Pings the store every `interval` and reconnects with exponential backoff
+/// once a ping fails. SQLite has nothing to supervise, so this returns at once.
+#[instrument(skip(db))]
+pub async fn supervise(db: Database, interval: Duration) {
+    let slot = match &db.store {
+        Store::SurrealDB(slot) => slot.clone(),
+        Store::Sqlite(_) => return,
+    };
+
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+
+        let conn = match slot.read() {
+            Ok(conn) => conn.clone(),
+            Err(_) => {
+                error!("connection slot is poisoned, stopping supervisor");
+                return;
+            }
+        };
+
+        match tokio::time::timeout(interval, conn.health()).await {
+            Ok(Ok(())) => continue,
+            Ok(Err(e)) => warn!(event = "store_health_check_failed", error = %e),
+            Err(_) => warn!(event = "store_health_check_failed", error = "timed out"),
+        }
+
+        db.set_state(ConnectionState::Reconnecting);
+
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt: u32 = 1;
+
+        loop {
+            match tokio::time::timeout(MAX_BACKOFF, db.connect()).await {
+                Ok(Ok(())) => break,
+                Ok(Err(e)) => warn!(event = "store_reconnect_failed", attempt, error = %e),
+                Err(_) => warn!(event = "store_reconnect_failed", attempt, error = "timed out"),
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            attempt += 1;
+        }
+
+        info!(event = "store_reconnected", attempt);
+    }
+}
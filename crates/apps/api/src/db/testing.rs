@@ -0,0 +1,43 @@
+use surrealdb::engine::any::{self, Any};
+use surrealdb::Surreal;
+use uuid::Uuid;
+
+use super::migrations::MIGRATIONS;
+use crate::models::{OperationStatus, Process};
+
+/// In-memory SurrealDB with every migration applied.
+pub async fn surreal_in_memory() -> Surreal<Any> {
+    let conn = any::connect("mem://").await.unwrap();
+    conn.use_ns("flowlocker").use_db("processes").await.unwrap();
+    for migration in MIGRATIONS {
+        conn.query(migration.statements).await.unwrap().check().unwrap();
+    }
+
+    conn
+}
+
+fn process(i: usize, status: OperationStatus, create_at: u64, updated_at: u64, sla: u64) -> Process {
+    Process {
+        process_id: Uuid::now_v7().to_string().into(),
+        app: "app".into(),
+        process_name: format!("job-{i}").into(),
+        status,
+        create_at,
+        updated_at,
+        ended_at: 0,
+        sla,
+    }
+}
+
+/// `n` processes at `now`, mostly finished ones kept for retention: 1%
+/// past their SLA, 1% finished long ago and 5% running.
+pub fn fixture(n: usize, now: u64) -> Vec<Process> {
+    (0..n)
+        .map(|i| match i % 100 {
+            0 => process(i, OperationStatus::New, now - 100, now - 100, 10),
+            1 => process(i, OperationStatus::Completed, now - 5000, now - 4000, 10),
+            2..=6 => process(i, OperationStatus::InProgress, now - 100, now - 100, 3600),
+            _ => process(i, OperationStatus::Completed, now - 100, now - 50, 10),
+        })
+        .collect()
+}
@@ -0,0 +1,99 @@
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{DeadLetter, Webhook, WebhookEvent};
+use crate::time::from_epoch;
+
+use super::error::{Error, Result};
+use super::{Backend, Database};
+
+#[instrument(skip(db, secret))]
+pub async fn create_webhook(
+    db: &Database,
+    url: String,
+    secret: String,
+    app: Option<String>,
+    process_name: Option<String>,
+    events: Vec<WebhookEvent>,
+) -> Result<Webhook> {
+    let webhook = Webhook {
+        webhook_id: Uuid::now_v7().to_string(),
+        url,
+        secret,
+        app,
+        process_name,
+        events,
+        create_at: from_epoch()?,
+    };
+
+    let conn = match db.backend()? {
+        Backend::SurrealDB(conn) => conn,
+        Backend::Sqlite(store) => {
+            store.create_webhook(webhook.clone()).await?;
+            return Ok(webhook);
+        }
+    };
+
+    let _: Option<Webhook> = conn
+        .create(("webhook", webhook.webhook_id.as_str()))
+        .content(webhook.clone())
+        .await?;
+
+    Ok(webhook)
+}
+
+pub async fn get_webhooks(db: &Database) -> Result<Vec<Webhook>> {
+    let conn = match db.backend()? {
+        Backend::SurrealDB(conn) => conn,
+        Backend::Sqlite(store) => return store.get_webhooks().await,
+    };
+
+    let mut response = conn
+        .query("SELECT * FROM webhook ORDER BY create_at, webhook_id")
+        .await?;
+
+    Ok(response.take(0)?)
+}
+
+#[instrument(skip(db))]
+pub async fn delete_webhook(db: &Database, id: &str) -> Result<()> {
+    let conn = match db.backend()? {
+        Backend::SurrealDB(conn) => conn,
+        Backend::Sqlite(store) => return store.delete_webhook(id).await,
+    };
+
+    let deleted: Option<Webhook> = conn.delete(("webhook", id)).await?;
+    if deleted.is_none() {
+        return Err(Error::RecordNotFound);
+    }
+
+    Ok(())
+}
+
+#[instrument(skip(db, letter), fields(delivery_id = %letter.delivery_id))]
+pub async fn insert_dead_letter(db: &Database, letter: DeadLetter) -> Result<()> {
+    let conn = match db.backend()? {
+        Backend::SurrealDB(conn) => conn,
+        Backend::Sqlite(store) => return store.insert_dead_letter(letter).await,
+    };
+
+    let _: Option<DeadLetter> = conn
+        .create(("webhook_dead_letter", letter.delivery_id.as_str()))
+        .content(letter)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_dead_letters(db: &Database) -> Result<Vec<DeadLetter>> {
+    let conn = match db.backend()? {
+        Backend::SurrealDB(conn) => conn,
+        Backend::Sqlite(store) => return store.get_dead_letters().await,
+    };
+
+    let mut response = conn
+        .query("SELECT * FROM webhook_dead_letter ORDER BY create_at, delivery_id")
+        .await?;
+
+    Ok(response.take(0)?)
+}
@@ -0,0 +1,27 @@
+use derive_more::From;
+use crate::{db, time};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, From)]
+pub enum Error {
+    InvalidName(String),
+    /// The election has no leader at the moment.
+    NoLeader(String),
+    /// The lock isn't the one of the current leader.
+    NotLeader(String),
+
+    #[from]
+    DB(db::error::Error),
+
+    #[from]
+    Time(time::error::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,261 @@
+//! Named leader elections for client services, with etcd's election semantics.
+//!
+//! The leader of election `name` is the holder of the reserved
+//! `flowlocker/election/<name>` lock: its lease is the lock SLA, renewed with
+//! lock heartbeats, and the leadership ends when the lock is released or
+//! outdated. The value proclaimed by the leader is kept next to it.
+
+pub mod error;
+
+use std::time::Duration;
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, info, instrument};
+
+use crate::db::elections::{get_election_value, set_election_value};
+use crate::db::repository::{
+    acquire_reserved_process, check_running_processes, get_process_by_id, outdate_expired_holders,
+    update_process_status, RESERVED_APP,
+};
+use crate::db::Database;
+use crate::models::{is_valid_name, ElectionValue, OperationStatus, Process, ResponseLeader};
+use crate::time::from_epoch;
+
+use self::error::{Error, Result};
+
+/// Longest wait between two attempts of a campaign, in case the end of the
+/// leadership wasn't published, e.g. a lease that expired unnoticed.
+const CAMPAIGN_RETRY: Duration = Duration::from_secs(1);
+
+/// Blocks until this candidate leads `name` and returns its leadership.
+///
+/// Candidates aren't queued: when the leader goes, any of them may win.
+/// Dropping the future withdraws the candidacy.
+#[instrument(skip(db, value))]
+pub async fn campaign(db: &Database, name: &str, value: String, lease: u64) -> Result<ResponseLeader> {
+    let lock_name = lock_name(name)?;
+    // Subscribed first, so the end of the current leadership can't be missed.
+    let mut events = db.subscribe();
+
+    loop {
+        match acquire_reserved_process(db, &lock_name, lease).await {
+            Ok(id) => {
+                let lock = get_process_by_id(db, &id).await?;
+                let value = ElectionValue {
+                    name: name.to_string(),
+                    lock_id: id,
+                    value,
+                    updated_at: from_epoch()?,
+                };
+                set_election_value(db, value.clone()).await?;
+                // Observers skip the lock until its value is stored.
+                db.publish_election(name);
+
+                info!(event = "elected", election = name, lock_id = %lock.process_id);
+                return Ok(ResponseLeader::new(&lock, &value));
+            }
+            Err(crate::db::error::Error::ProcessExist) => {
+                outdate_expired_holders(db, RESERVED_APP, &lock_name).await?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        let leadership_ended = async {
+            loop {
+                match events.recv().await {
+                    Ok(p) if is_lock_of(&p, &lock_name) && p.status.is_finished() => return,
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(_)) => return,
+                    Err(RecvError::Closed) => std::future::pending::<()>().await,
+                }
+            }
+        };
+
+        tokio::select! {
+            _ = leadership_ended => {}
+            _ = tokio::time::sleep(CAMPAIGN_RETRY) => {}
+        }
+    }
+}
+
+/// Replaces the value of the leader holding `lock_id`, without a new election.
+#[instrument(skip(db, value))]
+pub async fn proclaim(db: &Database, name: &str, lock_id: &str, value: String) -> Result<ResponseLeader> {
+    let lock = leader_lock(db, name, lock_id).await?;
+
+    let value = ElectionValue {
+        name: name.to_string(),
+        lock_id: lock_id.to_string(),
+        value,
+        updated_at: from_epoch()?,
+    };
+    set_election_value(db, value.clone()).await?;
+    db.publish_election(name);
+
+    Ok(ResponseLeader::new(&lock, &value))
+}
+
+/// Gives up the leadership held with `lock_id`, another candidate can be elected right away.
+#[instrument(skip(db))]
+pub async fn resign(db: &Database, name: &str, lock_id: &str) -> Result<()> {
+    leader_lock(db, name, lock_id).await?;
+    update_process_status(db, lock_id, OperationStatus::Completed).await?;
+
+    info!(event = "resigned", election = name, lock_id);
+    Ok(())
+}
+
+#[instrument(skip(db))]
+pub async fn leader(db: &Database, name: &str) -> Result<ResponseLeader> {
+    current_leader(db, &lock_name(name)?, name)
+        .await?
+        .ok_or_else(|| Error::NoLeader(format!("Election {name} has no leader")))
+}
+
+/// The current leader, if there is one, followed by every new leader and
+/// proclaimed value. Nothing is sent while the election has no leader.
+pub async fn observe(db: &Database, name: &str) -> Result<ReceiverStream<ResponseLeader>> {
+    let lock_name = lock_name(name)?;
+    let mut elections = db.subscribe_elections();
+    let first = current_leader(db, &lock_name, name).await?;
+
+    let (tx, rx) = mpsc::channel(16);
+    let db = db.clone();
+    let name = name.to_string();
+
+    tokio::spawn(async move {
+        let mut last = first.clone();
+        if let Some(leader) = first {
+            if tx.send(leader).await.is_err() {
+                return;
+            }
+        }
+
+        loop {
+            tokio::select! {
+                _ = tx.closed() => return,
+                election = elections.recv() => match election {
+                    Ok(election) if election == name => {}
+                    Ok(_) => continue,
+                    // Too slow to keep up: fall back to the stored state.
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return,
+                },
+            }
+
+            let leader = match current_leader(&db, &lock_name, &name).await {
+                Ok(leader) => leader,
+                Err(e) => {
+                    debug!(event = "can't read leader", election = name, error = ?e);
+                    continue;
+                }
+            };
+
+            // Rereads after lagging may find the leadership already sent.
+            let Some(leader) = leader.filter(|l| !last.as_ref().is_some_and(|last| same_leadership(l, last))) else {
+                continue;
+            };
+
+            last = Some(leader.clone());
+            if tx.send(leader).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(ReceiverStream::new(rx))
+}
+
+async fn current_leader(db: &Database, lock_name: &str, name: &str) -> Result<Option<ResponseLeader>> {
+    let Some(lock) = check_running_processes(db, RESERVED_APP, lock_name)
+        .await?
+        .and_then(|locks| locks.into_iter().next())
+    else {
+        return Ok(None);
+    };
+
+    // A leader whose lease is over is gone, even before it's outdated.
+    if lock.create_at + lock.sla < from_epoch()? {
+        return Ok(None);
+    }
+
+    Ok(get_election_value(db, name)
+        .await?
+        .filter(|value| value.lock_id == lock.process_id)
+        .map(|value| ResponseLeader::new(&lock, &value)))
+}
+
+/// The lock `lock_id` if it's the one of the current leader of `name`.
+async fn leader_lock(db: &Database, name: &str, lock_id: &str) -> Result<Process> {
+    let not_leader = || Error::NotLeader(format!("Lock {lock_id} doesn't lead election {name}"));
+
+    let lock = match get_process_by_id(db, lock_id).await {
+        Ok(lock) => lock,
+        Err(crate::db::error::Error::RecordNotFound) => return Err(not_leader()),
+        Err(e) => return Err(e.into()),
+    };
+
+    if !is_lock_of(&lock, &lock_name(name)?) || lock.status.is_finished() || lock.create_at + lock.sla < from_epoch()? {
+        return Err(not_leader());
+    }
+
+    Ok(lock)
+}
+
+fn same_leadership(a: &ResponseLeader, b: &ResponseLeader) -> bool {
+    a.lock_id == b.lock_id && a.value == b.value
+}
+
+fn is_lock_of(p: &Process, lock_name: &str) -> bool {
+    p.app == RESERVED_APP && p.process_name == lock_name
+}
+
+/// `election/<name>`, for a name passing [`is_valid_name`].
+fn lock_name(name: &str) -> Result<String> {
+    if !is_valid_name(name) {
+        return Err(Error::InvalidName(format!("Invalid election name {name}")));
+    }
+
+    Ok(format!("election/{name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_leadership_passes_to_the_next_candidate() {
+        let db = Database::in_memory();
+
+        let first = campaign(&db, "primary", "a:8080".to_string(), 30).await.unwrap();
+        let mut leaders = observe(&db, "primary").await.unwrap();
+        assert_eq!(leaders.next().await.unwrap().value, "a:8080");
+
+        let candidate = {
+            let db = db.clone();
+            tokio::spawn(async move { campaign(&db, "primary", "b:8080".to_string(), 30).await })
+        };
+
+        // Proclaiming doesn't change the lock, lock subscribers hear nothing.
+        let mut lock_events = db.subscribe();
+        let proclaimed = proclaim(&db, "primary", &first.lock_id, "a:9090".to_string()).await.unwrap();
+        assert!(lock_events.try_recv().is_err());
+        assert_eq!(proclaimed.lock_id, first.lock_id);
+        assert_eq!(leaders.next().await.unwrap().value, "a:9090");
+        assert!(!candidate.is_finished());
+
+        resign(&db, "primary", &first.lock_id).await.unwrap();
+        let second = candidate.await.unwrap().unwrap();
+        assert_eq!(leaders.next().await.unwrap(), second);
+        assert_eq!(leader(&db, "primary").await.unwrap(), second);
+
+        let res = proclaim(&db, "primary", &first.lock_id, "a:8080".to_string()).await;
+        assert!(matches!(res, Err(Error::NotLeader(_))));
+        assert!(matches!(leader(&db, "other").await, Err(Error::NoLeader(_))));
+        assert!(matches!(leader(&db, "no/slash").await, Err(Error::InvalidName(_))));
+    }
+}
@@ -34,6 +34,15 @@ pub enum Error {
 
     #[from]
     Scheduler(super::scheduler::error::Error),
+
+    #[from]
+    Grpc(crate::grpc::error::Error),
+
+    #[from]
+    Task(tokio::task::JoinError),
+
+    /// Servers or the scheduler were still busy when the shutdown deadline passed.
+    ShutdownTimeout,
 }
 
 
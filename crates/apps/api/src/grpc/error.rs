@@ -0,0 +1,71 @@
+use derive_more::From;
+use tonic::Status;
+use tracing::error;
+
+use crate::db;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, From)]
+pub enum Error {
+    #[from]
+    Transport(tonic::transport::Error),
+
+    #[from]
+    AddrParse(std::net::AddrParseError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Transport(e) => Some(e),
+            Error::AddrParse(e) => Some(e),
+        }
+    }
+}
+
+impl From<db::error::Error> for Status {
+    fn from(err: db::error::Error) -> Self {
+        match err {
+            db::error::Error::ProcessExist => Status::already_exists("Process already exists"),
+            db::error::Error::RecordNotFound => Status::not_found("Process not found"),
+            db::error::Error::StoreUnavailable => Status::unavailable("Storage is unavailable"),
+            db::error::Error::InvalidStatus(e) => Status::failed_precondition(e),
+            db::error::Error::Reserved(e) => Status::invalid_argument(e),
+            db::error::Error::AlreadyDone { process_id, completed_at } => Status::failed_precondition(format!(
+                "Already done by {process_id}, completed at {completed_at}"
+            )),
+            db::error::Error::CoolingDown { retry_after } => {
+                let mut status = Status::resource_exhausted(format!("Cooling down, retry in {retry_after}s"));
+                status.metadata_mut().insert("retry-after", retry_after.into());
+                status
+            }
+            // Driver details stay in the logs.
+            e => {
+                error!("grpc error: {e:?}");
+                Status::internal("Something went wrong")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::Code;
+
+    use super::*;
+
+    #[test]
+    fn test_storage_failures_are_not_exposed() {
+        let status = Status::from(db::error::Error::Sqlite("disk I/O error".to_string()));
+        assert_eq!((status.code(), status.message()), (Code::Internal, "Something went wrong"));
+
+        assert_eq!(Status::from(db::error::Error::StoreUnavailable).code(), Code::Unavailable);
+    }
+}
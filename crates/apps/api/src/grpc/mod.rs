@@ -0,0 +1,9 @@
+pub mod error;
+pub mod server;
+// Handlers answer with tonic's `Status`, large but not ours to shrink.
+#[allow(clippy::result_large_err)]
+mod service;
+
+pub mod pb {
+    tonic::include_proto!("flowlocker.v1");
+}
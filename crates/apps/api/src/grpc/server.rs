@@ -0,0 +1,27 @@
+use std::net::SocketAddr;
+
+use tokio_util::sync::CancellationToken;
+use tonic::transport::Server;
+use tracing::info;
+
+use crate::config::config;
+use crate::db::cooldowns::CooldownPolicy;
+use crate::db::Database;
+
+use super::error::Result;
+use super::pb::locks_server::LocksServer;
+use super::service::LockService;
+
+/// Serves until `shutdown` is cancelled, then waits for in-flight calls.
+pub async fn new_server(db: Database, port: u16, shutdown: CancellationToken) -> Result<()> {
+    let addr: SocketAddr = format!("0.0.0.0:{port}").parse()?;
+
+    info!("Starting gRPC server on port {}", port);
+
+    Server::builder()
+        .add_service(LocksServer::new(LockService::new(db, CooldownPolicy::new(config().cooldowns.clone()))))
+        .serve_with_shutdown(addr, shutdown.cancelled_owned())
+        .await?;
+
+    Ok(())
+}
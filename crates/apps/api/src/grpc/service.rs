@@ -0,0 +1,342 @@
+use std::pin::Pin;
+
+use prost_types::Timestamp;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::cooldowns::CooldownPolicy;
+use crate::db::locks::{acquire_lock, Acquisition};
+use crate::db::repository::{
+    get_process_by_id, get_processes, set_process_status, Cursor, ProcessFilter,
+    SortField, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+};
+use crate::db::events::watch_process;
+use crate::db::Database;
+use crate::models::{OperationStatus, Process};
+use crate::time::parse_duration;
+use lib_query_builder::builder::Order;
+
+use super::pb::locks_server::Locks;
+use super::pb::{
+    self, AcquireRequest, ListRequest, ListResponse, Lock, LockRequest, LockStatus,
+    UpdateStatusRequest,
+};
+
+pub struct LockService {
+    db: Database,
+    cooldowns: CooldownPolicy,
+}
+
+impl LockService {
+    pub fn new(db: Database, cooldowns: CooldownPolicy) -> Self {
+        LockService { db, cooldowns }
+    }
+
+    async fn set_status(&self, lock_id: &str, status: OperationStatus) -> Result<Response<Lock>, Status> {
+        let id = parse_lock_id(lock_id)?;
+
+        set_process_status(&self.db, &id, status).await?;
+        let lock = get_process_by_id(&self.db, &id).await?;
+
+        Ok(Response::new(lock.into()))
+    }
+}
+
+type WatchStream = Pin<Box<dyn Stream<Item = Result<Lock, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Locks for LockService {
+    #[instrument(skip(self))]
+    async fn acquire(&self, request: Request<AcquireRequest>) -> Result<Response<Lock>, Status> {
+        let acquisition = to_acquisition(request.into_inner())?;
+
+        let id = acquire_lock(&self.db, &self.cooldowns, acquisition).await?;
+        let lock = get_process_by_id(&self.db, &id).await?;
+
+        Ok(Response::new(lock.into()))
+    }
+
+    #[instrument(skip(self))]
+    async fn get(&self, request: Request<LockRequest>) -> Result<Response<Lock>, Status> {
+        let id = parse_lock_id(&request.into_inner().lock_id)?;
+
+        let lock = get_process_by_id(&self.db, &id).await?;
+
+        Ok(Response::new(lock.into()))
+    }
+
+    #[instrument(skip(self))]
+    async fn list(&self, request: Request<ListRequest>) -> Result<Response<ListResponse>, Status> {
+        let filter = to_filter(request.into_inner())?;
+
+        let page = get_processes(&self.db, filter).await?;
+
+        Ok(Response::new(ListResponse {
+            locks: page.processes.into_iter().map(Lock::from).collect(),
+            next_cursor: page.next_cursor.as_ref().map(Cursor::encode).unwrap_or_default(),
+        }))
+    }
+
+    #[instrument(skip(self))]
+    async fn update_status(&self, request: Request<UpdateStatusRequest>) -> Result<Response<Lock>, Status> {
+        let req = request.into_inner();
+
+        self.set_status(&req.lock_id, to_operation_status(req.status)?).await
+    }
+
+    #[instrument(skip(self))]
+    async fn release(&self, request: Request<LockRequest>) -> Result<Response<Lock>, Status> {
+        self.set_status(&request.into_inner().lock_id, OperationStatus::Completed).await
+    }
+
+    type WatchLockStream = WatchStream;
+
+    #[instrument(skip(self))]
+    async fn watch_lock(&self, request: Request<LockRequest>) -> Result<Response<Self::WatchLockStream>, Status> {
+        let id = parse_lock_id(&request.into_inner().lock_id)?;
+
+        let changes = watch_process(&self.db, &id).await?;
+
+        Ok(Response::new(Box::pin(changes.map(|p| Ok(p.into()))) as Self::WatchLockStream))
+    }
+}
+
+fn parse_lock_id(lock_id: &str) -> Result<String, Status> {
+    Uuid::parse_str(lock_id)
+        .map(|id| id.to_string())
+        .map_err(|_| Status::invalid_argument("Invalid lock id"))
+}
+
+fn to_acquisition(req: AcquireRequest) -> Result<Acquisition, Status> {
+    let duration = |value: &str, name: &str| {
+        parse_duration(value).map_err(|_| Status::invalid_argument(format!("Invalid {name} format")))
+    };
+
+    let dedupe = match (req.dedupe_key, req.window) {
+        (None, None) => None,
+        (Some(key), Some(window)) if !key.is_empty() => Some((key, duration(&window, "window")?)),
+        _ => return Err(Status::invalid_argument("dedupe_key and window must be set together")),
+    };
+
+    Ok(Acquisition {
+        eta: duration(&req.eta, "ETA")?,
+        dedupe,
+        cooldown: req.cooldown.as_deref().map(|c| duration(c, "cooldown")).transpose()?,
+        app: req.app,
+        process: req.process,
+    })
+}
+
+fn to_filter(req: ListRequest) -> Result<ProcessFilter, Status> {
+    let limit = req.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(Status::invalid_argument(format!("limit must be between 1 and {MAX_PAGE_SIZE}")));
+    }
+
+    let cursor = match req.cursor.as_deref() {
+        Some(cursor) => Some(Cursor::decode(cursor).ok_or_else(|| Status::invalid_argument("Invalid cursor"))?),
+        None => None,
+    };
+
+    let sort = match req.order_by() {
+        pb::SortField::CreateAt => SortField::CreateAt,
+        pb::SortField::UpdatedAt => SortField::UpdatedAt,
+    };
+    let order = match req.order() {
+        pb::SortOrder::Asc => Order::Asc,
+        pb::SortOrder::Desc => Order::Desc,
+    };
+
+    Ok(ProcessFilter {
+        app: req.app,
+        process_name: req.process,
+        statuses: req
+            .statuses
+            .into_iter()
+            .map(to_operation_status)
+            .collect::<Result<_, _>>()?,
+        created_after: req.created_after.map(|t| t.seconds.max(0) as u64),
+        created_before: req.created_before.map(|t| t.seconds.max(0) as u64),
+        sort,
+        order,
+        cursor,
+        limit,
+    })
+}
+
+fn to_operation_status(status: i32) -> Result<OperationStatus, Status> {
+    match LockStatus::try_from(status) {
+        Ok(LockStatus::New) => Ok(OperationStatus::New),
+        Ok(LockStatus::InProgress) => Ok(OperationStatus::InProgress),
+        Ok(LockStatus::Completed) => Ok(OperationStatus::Completed),
+        Ok(LockStatus::Canceled) => Ok(OperationStatus::Canceled),
+        Ok(LockStatus::Outdated) => Ok(OperationStatus::Outdated),
+        Ok(LockStatus::Unspecified) | Err(_) => Err(Status::invalid_argument("bad operational status")),
+    }
+}
+
+impl From<OperationStatus> for LockStatus {
+    fn from(status: OperationStatus) -> Self {
+        match status {
+            OperationStatus::New => LockStatus::New,
+            OperationStatus::InProgress => LockStatus::InProgress,
+            OperationStatus::Completed => LockStatus::Completed,
+            OperationStatus::Canceled => LockStatus::Canceled,
+            OperationStatus::Outdated => LockStatus::Outdated,
+        }
+    }
+}
+
+fn timestamp(seconds: u64) -> Timestamp {
+    Timestamp {
+        seconds: seconds as i64,
+        nanos: 0,
+    }
+}
+
+impl From<Process> for Lock {
+    fn from(p: Process) -> Self {
+        Lock {
+            process_id: p.process_id.into_owned(),
+            app: p.app.into_owned(),
+            process_name: p.process_name.into_owned(),
+            status: LockStatus::from(p.status).into(),
+            create_at: Some(timestamp(p.create_at)),
+            updated_at: Some(timestamp(p.updated_at)),
+            ended_at: (p.ended_at != 0).then(|| timestamp(p.ended_at)),
+            sla: p.sla,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::Code;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lock_lifecycle() {
+        let service = LockService::new(Database::in_memory(), CooldownPolicy::default());
+        let acquire = || {
+            Request::new(AcquireRequest {
+                app: "billing".to_string(),
+                process: "invoices".to_string(),
+                eta: "60s".to_string(),
+                ..Default::default()
+            })
+        };
+
+        let lock = service.acquire(acquire()).await.unwrap().into_inner();
+        assert_eq!(lock.status(), LockStatus::New);
+
+        let err = service.acquire(acquire()).await.unwrap_err();
+        assert_eq!(err.code(), Code::AlreadyExists);
+
+        let mut watch = service
+            .watch_lock(Request::new(LockRequest { lock_id: lock.process_id.clone() }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(watch.next().await.unwrap().unwrap().status(), LockStatus::New);
+
+        let released = service
+            .release(Request::new(LockRequest { lock_id: lock.process_id.clone() }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(released.status(), LockStatus::Completed);
+        assert!(released.ended_at.is_some());
+
+        assert_eq!(watch.next().await.unwrap().unwrap().status(), LockStatus::Completed);
+        assert!(watch.next().await.is_none());
+
+        let page = service
+            .list(Request::new(ListRequest {
+                app: Some("billing".to_string()),
+                statuses: vec![LockStatus::Completed.into()],
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(page.locks.len(), 1);
+        assert!(page.next_cursor.is_empty());
+
+        let err = service
+            .get(Request::new(LockRequest { lock_id: "not-a-uuid".to_string() }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_dedupes_and_cools_down() {
+        let policy = CooldownPolicy::new([("crm/sync".to_string(), 3600)].into());
+        let service = LockService::new(Database::in_memory(), policy);
+        let acquire = |process: &str, dedupe_key: Option<&str>| {
+            Request::new(AcquireRequest {
+                app: "crm".to_string(),
+                process: process.to_string(),
+                eta: "60s".to_string(),
+                dedupe_key: dedupe_key.map(str::to_string),
+                window: dedupe_key.map(|_| "24h".to_string()),
+                cooldown: None,
+            })
+        };
+        let release = |lock: Lock| Request::new(LockRequest { lock_id: lock.process_id });
+
+        let lock = service.acquire(acquire("report", Some("report:2026-10-18"))).await.unwrap().into_inner();
+        service.release(release(lock)).await.unwrap();
+        let err = service.acquire(acquire("report", Some("report:2026-10-18"))).await.unwrap_err();
+        assert_eq!(err.code(), Code::FailedPrecondition);
+
+        let lock = service.acquire(acquire("sync", None)).await.unwrap().into_inner();
+        service.release(release(lock)).await.unwrap();
+        let err = service.acquire(acquire("sync", None)).await.unwrap_err();
+        assert_eq!(err.code(), Code::ResourceExhausted);
+        let retry_after: u64 = err.metadata().get("retry-after").unwrap().to_str().unwrap().parse().unwrap();
+        assert!((3590..=3600).contains(&retry_after));
+    }
+
+    #[tokio::test]
+    async fn test_lost_dedupe_races_publish_nothing() {
+        let db = Database::in_memory();
+        let mut events = db.subscribe();
+        let service = std::sync::Arc::new(LockService::new(db, CooldownPolicy::default()));
+
+        // The race is lost in the store by all but one, a few rounds make it likely.
+        for round in 0..4 {
+            let racers: Vec<_> = (0..16)
+                .map(|i| {
+                    let service = service.clone();
+                    tokio::spawn(async move {
+                        let request = Request::new(AcquireRequest {
+                            app: "billing".to_string(),
+                            process: format!("report-{round}-{i}"),
+                            eta: "60s".to_string(),
+                            dedupe_key: Some(format!("report:{round}")),
+                            window: Some("24h".to_string()),
+                            cooldown: None,
+                        });
+                        service.acquire(request).await
+                    })
+                })
+                .collect();
+
+            let mut won = Vec::new();
+            for racer in racers {
+                match racer.await.unwrap() {
+                    Ok(lock) => won.push(lock.into_inner().process_id),
+                    Err(err) => assert_eq!(err.code(), Code::AlreadyExists),
+                }
+            }
+            assert_eq!(won.len(), 1);
+
+            assert_eq!(events.try_recv().unwrap().process_id.as_ref(), won[0].as_str());
+            assert!(events.try_recv().is_err());
+        }
+    }
+}
@@ -1,13 +1,19 @@
+mod coordination;
 mod db;
+mod elections;
 mod error;
+mod grpc;
 mod logger;
 mod models;
 mod rest_api;
 mod scheduler;
 mod time;
 mod config;
+mod webhooks;
 
 use tokio::signal;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
 
 use lib_core::tracing;
 
@@ -15,11 +21,13 @@ use config::config;
 
 use tracing_subscriber::EnvFilter;
 
-use log::{info, warn};
+use log::{error, info, warn};
 use tracing_error::ErrorLayer;
 
 pub use self::error::{Error, Result};
-use crate::scheduler::Scheduler;
+use crate::scheduler::{
+    ArchiveExport, Cleaner, Deadlines, Election, MetricsRollup, Registry, Retention, Scheduler, SlaWarnings,
+};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
@@ -50,16 +58,109 @@ async fn main() -> Result<()> {
     let database = db::new().await?;
     database.connect().await?;
 
-    let scheduler = Scheduler::new(database.clone(), config().sch_interval);
+    let applied = db::migrations::migrate(&database).await?;
+    info!("Schema is up to date, {} migration(s) applied", applied);
 
-    scheduler.start().await?;
+    if std::env::args().any(|arg| arg == "--migrate-only") {
+        return Ok(());
+    }
+
+    tokio::spawn(db::supervise(database.clone(), config().db_health_interval));
+
+    let dispatcher = webhooks::Dispatcher::new(
+        database.clone(),
+        webhooks::RetryPolicy {
+            max_attempts: config().webhook_max_attempts,
+            backoff: config().webhook_backoff,
+        },
+    );
+    dispatcher.clone().spawn();
+
+    let sla_warnings = SlaWarnings {
+        thresholds: config().sla_warning_thresholds.clone(),
+        webhooks: config().sla_warning_webhook.then_some(dispatcher),
+    };
+    let mut registry = Registry::new(config().scheduler_backoff)
+        .dry_run(config().cleaner_dry_run)
+        .register(Cleaner::new(database.clone(), config().sch_interval, sla_warnings))
+        .register(Retention::new(database.clone(), config().sch_interval, config().retention))
+        .register(MetricsRollup::new(database.clone(), config().rollup_interval));
+    if let Some(dir) = &config().archive_dir {
+        registry = registry.register(ArchiveExport::new(database.clone(), dir, config().archive_schedule.clone()));
+    }
+    let election = Election::new(database.clone(), config().scheduler_lease);
+    let scheduler = Scheduler::new(registry.clone()).leadership(election.leadership());
+    let deadlines = Deadlines::new(database.clone()).leadership(election.leadership());
+
+    let shutdown = CancellationToken::new();
+    let mut tasks = JoinSet::new();
 
-    let _run_axum = tokio::spawn(rest_api::server::new_server(database));
+    let stop = shutdown.clone();
+    tasks.spawn(async move { Ok(scheduler.run(stop).await?) });
+
+    let stop = shutdown.clone();
+    tasks.spawn(async move {
+        election.run(stop).await;
+        Ok(())
+    });
+
+    let stop = shutdown.clone();
+    tasks.spawn(async move {
+        deadlines.run(stop).await;
+        Ok(())
+    });
+
+    let stop = shutdown.clone();
+    let db = database.clone();
+    tasks.spawn(async move { Ok(grpc::server::new_server(db, config().grpc_port, stop).await?) });
+
+    let stop = shutdown.clone();
+    tasks.spawn(async move { Ok(rest_api::server::new_server(database, registry, stop).await?) });
 
     info!("Listening for signals");
 
-    shutdown_signal().await;
-    Ok(())
+    // A server that stops by itself (e.g. its port is taken) brings the others down.
+    let mut result = tokio::select! {
+        _ = shutdown_signal() => Ok(()),
+        Some(res) = tasks.join_next() => {
+            error!("a server stopped before shutdown was requested");
+            flatten(res).and(Err(Error::custom("server stopped unexpectedly")))
+        }
+    };
+
+    shutdown.cancel();
+
+    match tokio::time::timeout(config().shutdown_timeout, drain(&mut tasks)).await {
+        Ok(drained) => result = result.and(drained),
+        Err(_) => {
+            warn!("shutdown deadline of {:?} exceeded, aborting", config().shutdown_timeout);
+            tasks.abort_all();
+            result = result.and(Err(Error::ShutdownTimeout));
+        }
+    }
+
+    // Spans of the last requests are still queued in the batch exporter.
+    if tokio::task::spawn_blocking(tracing::shutdown_opentelemetry).await.is_err() {
+        warn!("can't flush pending spans");
+    }
+
+    info!("Shutdown complete");
+    result
+}
+
+/// Waits for every task, returns the first failure.
+async fn drain(tasks: &mut JoinSet<Result<()>>) -> Result<()> {
+    let mut result = Ok(());
+
+    while let Some(res) = tasks.join_next().await {
+        result = result.and(flatten(res));
+    }
+
+    result
+}
+
+fn flatten(res: core::result::Result<Result<()>, tokio::task::JoinError>) -> Result<()> {
+    res?
 }
 
 async fn shutdown_signal() {
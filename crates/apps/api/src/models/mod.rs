@@ -3,8 +3,11 @@ use std::cmp::PartialEq;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Debug)]
+pub use lib_models::lock::{OperationStatus, ResponseProcess};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Process {
     pub process_id: Cow<'static, str>,
     pub app: Cow<'static, str>,
@@ -16,19 +19,6 @@ pub struct Process {
     pub sla: u64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct ResponseProcess {
-    pub process_id: Cow<'static, str>,
-    pub app: Cow<'static, str>,
-    pub process_name: Cow<'static, str>,
-    pub status: OperationStatus,
-    pub create_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub ended_at: Option<DateTime<Utc>>,
-    pub sla: u64,
-}
-
 impl Process {
     pub fn to_response(&self) -> ResponseProcess {
         let ended_at: Option<DateTime<Utc>> = if self.ended_at != 0 {
@@ -51,54 +41,294 @@ impl Process {
     }
 }
 
+/// Subscription to lock events, optionally narrowed to one app and/or process name.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Webhook {
+    pub webhook_id: String,
+    pub url: String,
+    /// HMAC-SHA256 key for the `X-Flowlocker-Signature` header, never returned by the API.
+    pub secret: String,
+    pub app: Option<String>,
+    pub process_name: Option<String>,
+    pub events: Vec<WebhookEvent>,
+    pub create_at: u64,
+}
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
-pub enum OperationStatus {
-    New,
-    InProgress,
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ResponseWebhook {
+    #[schema(format = Uuid)]
+    pub webhook_id: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub process_name: Option<String>,
+    pub events: Vec<WebhookEvent>,
+    pub create_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    pub fn matches(&self, event: WebhookEvent, p: &Process) -> bool {
+        self.events.contains(&event)
+            && self.app.as_deref().is_none_or(|app| p.app == app)
+            && self.process_name.as_deref().is_none_or(|name| p.process_name == name)
+    }
+
+    pub fn to_response(&self) -> ResponseWebhook {
+        ResponseWebhook {
+            webhook_id: self.webhook_id.clone(),
+            url: self.url.clone(),
+            app: self.app.clone(),
+            process_name: self.process_name.clone(),
+            events: self.events.clone(),
+            create_at: DateTime::from_timestamp(self.create_at as i64, 0).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Acquired,
     Completed,
     Canceled,
     Outdated,
+    /// A running process consumed one of the configured shares of its SLA.
+    SlaWarning,
 }
 
-// impl PartialEq<OperationStatus> for &OperationStatus {
-//     fn eq(&self, other: &OperationStatus) -> bool {
-//         todo!()
-//     }
-// }
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 5] = [
+        WebhookEvent::Acquired,
+        WebhookEvent::Completed,
+        WebhookEvent::Canceled,
+        WebhookEvent::Outdated,
+        WebhookEvent::SlaWarning,
+    ];
 
-impl OperationStatus {
-    pub fn is_canceled(&self) -> bool {
-        if self.to_string() == OperationStatus::Canceled.to_string() {
-            return true;
+    /// Event announced when a process reaches `status`, `InProgress` isn't announced.
+    pub fn for_status(status: &OperationStatus) -> Option<WebhookEvent> {
+        match status {
+            OperationStatus::New => Some(WebhookEvent::Acquired),
+            OperationStatus::InProgress => None,
+            OperationStatus::Completed => Some(WebhookEvent::Completed),
+            OperationStatus::Canceled => Some(WebhookEvent::Canceled),
+            OperationStatus::Outdated => Some(WebhookEvent::Outdated),
         }
-        false
     }
+}
+
+impl std::fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebhookEvent::Acquired => write!(f, "acquired"),
+            WebhookEvent::Completed => write!(f, "completed"),
+            WebhookEvent::Canceled => write!(f, "canceled"),
+            WebhookEvent::Outdated => write!(f, "outdated"),
+            WebhookEvent::SlaWarning => write!(f, "sla_warning"),
+        }
+    }
+}
+
+impl std::str::FromStr for WebhookEvent {
+    type Err = String;
 
-    pub fn is_completed(&self) -> bool {
-        if self.to_string() == OperationStatus::Completed.to_string() {
-            return true;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "acquired" => Ok(WebhookEvent::Acquired),
+            "completed" => Ok(WebhookEvent::Completed),
+            "canceled" => Ok(WebhookEvent::Canceled),
+            "outdated" => Ok(WebhookEvent::Outdated),
+            "sla_warning" => Ok(WebhookEvent::SlaWarning),
+            _ => Err(format!("unknown webhook event: {s}")),
         }
-        false
     }
+}
+
+/// Delivery that still failed after the last retry.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct DeadLetter {
+    #[schema(format = Uuid)]
+    pub delivery_id: String,
+    #[schema(format = Uuid)]
+    pub webhook_id: String,
+    pub url: String,
+    pub event: WebhookEvent,
+    /// Signed JSON body of the last attempt.
+    pub payload: String,
+    pub attempts: u32,
+    pub last_error: String,
+    pub create_at: u64,
+}
+
+/// Whether `name` can name an election, a barrier or a latch: 1 to 128
+/// letters, digits, `-`, `_` and `.`.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 128
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Value proclaimed by the leader of an election, one per election.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ElectionValue {
+    pub name: String,
+    /// Lock held by the leader that proclaimed it.
+    pub lock_id: String,
+    pub value: String,
+    pub updated_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ResponseLeader {
+    #[schema(example = "billing-primary")]
+    pub election: String,
+    /// Lock held by the leader, kept with `POST /v1/locks/{lock_id}/heartbeat`.
+    #[schema(format = Uuid)]
+    pub lock_id: String,
+    #[schema(example = "10.0.3.17:8080")]
+    pub value: String,
+    pub elected_at: DateTime<Utc>,
+    /// End of the lease unless renewed.
+    pub expires_at: DateTime<Utc>,
+}
 
-    //TODO Change Staled to Outdated in whole app
-    pub fn is_outdated(&self) -> bool {
-        if self.to_string() == OperationStatus::Outdated.to_string() {
-            return true;
+impl ResponseLeader {
+    pub fn new(lock: &Process, value: &ElectionValue) -> Self {
+        ResponseLeader {
+            election: value.name.clone(),
+            lock_id: lock.process_id.to_string(),
+            value: value.value.clone(),
+            elected_at: DateTime::from_timestamp(lock.create_at as i64, 0).unwrap_or_default(),
+            expires_at: DateTime::from_timestamp((lock.create_at + lock.sla) as i64, 0).unwrap_or_default(),
         }
-        false
     }
 }
 
-impl std::fmt::Display for OperationStatus {
+/// Table of a countdown, the name is its record id.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub enum CountdownKind {
+    /// Released when the last of `total` participants arrives.
+    Barrier,
+    /// Released once counted down `total` times.
+    Latch,
+}
+
+impl CountdownKind {
+    pub fn table(&self) -> &'static str {
+        match self {
+            CountdownKind::Barrier => "barrier",
+            CountdownKind::Latch => "latch",
+        }
+    }
+}
+
+impl std::fmt::Display for CountdownKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            OperationStatus::New => write!(f, "New"),
-            OperationStatus::Completed => write!(f, "Completed"),
-            OperationStatus::InProgress => write!(f, "InProgress"),
-            OperationStatus::Outdated => write!(f, "Outdated"),
-            OperationStatus::Canceled => write!(f, "Staled"),
+            CountdownKind::Barrier => write!(f, "Barrier"),
+            CountdownKind::Latch => write!(f, "Latch"),
+        }
+    }
+}
+
+/// Barrier or countdown latch. Its status isn't stored, it follows from
+/// `remaining` and `timeout_at`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Countdown {
+    pub name: String,
+    pub total: u32,
+    pub remaining: u32,
+    pub create_at: u64,
+    pub timeout_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy, ToSchema)]
+pub enum CountdownStatus {
+    Waiting,
+    Released,
+    /// `timeout_at` passed before it was released, it never will be.
+    TimedOut,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ResponseCountdown {
+    #[schema(example = "nightly-shards")]
+    pub name: String,
+    /// Participants or count downs needed for the release.
+    pub total: u32,
+    pub remaining: u32,
+    pub status: CountdownStatus,
+    pub create_at: DateTime<Utc>,
+    pub timeout_at: DateTime<Utc>,
+}
+
+impl Countdown {
+    pub fn status(&self, now: u64) -> CountdownStatus {
+        if self.remaining == 0 {
+            CountdownStatus::Released
+        } else if self.timeout_at < now {
+            CountdownStatus::TimedOut
+        } else {
+            CountdownStatus::Waiting
+        }
+    }
+
+    pub fn to_response(&self, now: u64) -> ResponseCountdown {
+        ResponseCountdown {
+            name: self.name.clone(),
+            total: self.total,
+            remaining: self.remaining,
+            status: self.status(now),
+            create_at: DateTime::from_timestamp(self.create_at as i64, 0).unwrap_or_default(),
+            timeout_at: DateTime::from_timestamp(self.timeout_at as i64, 0).unwrap_or_default(),
+        }
+    }
+}
+
+/// Runs whose end opens a window on their key, one table each.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunKind {
+    /// Keyed by dedupe key, the key is done for `window` once the run completed.
+    Dedupe,
+    /// Keyed by process name, it can't be acquired for `window` once the run finished.
+    Cooldown,
+}
+
+impl RunKind {
+    pub fn table(&self) -> &'static str {
+        match self {
+            RunKind::Dedupe => "dedupe",
+            RunKind::Cooldown => "cooldown",
+        }
+    }
+
+    /// Columns of the key, the window and the end of a run in its table.
+    pub fn columns(&self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            RunKind::Dedupe => ("dedupe_key", "window", "completed_at"),
+            RunKind::Cooldown => ("process_name", "cooldown", "ended_at"),
         }
     }
-}
\ No newline at end of file
+
+    /// Whether a process changing to `status` ends its run.
+    pub fn ends(&self, status: &OperationStatus) -> bool {
+        match self {
+            RunKind::Dedupe => status.is_completed(),
+            RunKind::Cooldown => status.is_finished(),
+        }
+    }
+}
+
+/// Latest run of a key of a [`RunKind`], one per app and key.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Run {
+    pub app: String,
+    pub key: String,
+    pub process_id: String,
+    /// Seconds after `ended_at` during which the window stays open.
+    pub window: u64,
+    /// 0 until the run ended.
+    pub ended_at: u64,
+}
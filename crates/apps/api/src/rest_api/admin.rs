@@ -0,0 +1,120 @@
+use axum::extract::rejection::PathRejection;
+use axum::extract::{Path, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+
+use crate::scheduler::{PlannedChange, Registry, TaskRun, TaskStatus};
+
+use super::v1::{Envelope, Result};
+
+/// Scheduler introspection, meant for operators.
+pub fn routes(registry: Registry) -> Router {
+    Router::new()
+        .route("/api/admin/scheduler", get(scheduler_status))
+        .route("/api/admin/scheduler/dry-run", get(dry_run))
+        .route("/api/admin/scheduler/:task/run", post(run_task))
+        .with_state(registry)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/scheduler",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every registered task", body = SchedulerEnvelope),
+    )
+)]
+pub(super) async fn scheduler_status(State(registry): State<Registry>) -> Json<Envelope<Vec<TaskStatus>>> {
+    Json(Envelope::new(registry.status().await))
+}
+
+/// What the tasks would outdate or delete if they ran now. Nothing is changed.
+#[utoipa::path(
+    get,
+    path = "/api/admin/scheduler/dry-run",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Processes that would be outdated or deleted", body = PlanEnvelope),
+        (status = 500, description = "The tasks couldn't read the processes", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn dry_run(State(registry): State<Registry>) -> Result<Json<Envelope<Vec<PlannedChange>>>> {
+    Ok(Json(Envelope::new(registry.plan().await?)))
+}
+
+/// Runs the task now and waits for it, even while it's backing off after failures.
+#[utoipa::path(
+    post,
+    path = "/api/admin/scheduler/{task}/run",
+    tag = "admin",
+    params(("task" = String, Path, description = "Task name", example = "expiry")),
+    responses(
+        (status = 200, description = "The run, failed runs included", body = TaskRunEnvelope),
+        (status = 404, description = "Unknown task", body = ErrorEnvelope),
+        (status = 409, description = "The task is already running", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn run_task(
+    State(registry): State<Registry>,
+    task: core::result::Result<Path<String>, PathRejection>,
+) -> Result<Json<Envelope<TaskRun>>> {
+    let Path(task) = task?;
+
+    let run = registry.run_now(&task).await?;
+
+    Ok(Json(Envelope::new(run)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::db::Database;
+    use crate::scheduler::{Cleaner, SlaWarnings};
+
+    async fn send(app: &Router, method: &str, uri: &str) -> (StatusCode, Value) {
+        let req = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+
+        let res = app.clone().oneshot(req).await.unwrap();
+        let status = res.status();
+        let bytes = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_manual_run_is_reported() {
+        let registry = Registry::new(Duration::from_secs(30)).register(Cleaner::new(
+            Database::in_memory(),
+            Duration::from_secs(30),
+            SlaWarnings::default(),
+        ));
+        let app = routes(registry);
+
+        let (status, body) = send(&app, "GET", "/api/admin/scheduler").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"][0]["name"], "expiry");
+        assert_eq!(body["data"][0]["schedule"], "every 30s");
+        assert!(body["data"][0]["last_run"].is_null());
+
+        let (status, body) = send(&app, "POST", "/api/admin/scheduler/expiry/run").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["outcome"], "succeeded");
+        assert_eq!(body["data"]["manual"], true);
+
+        let (_, body) = send(&app, "GET", "/api/admin/scheduler").await;
+        assert_eq!(body["data"][0]["last_run"]["outcome"], "succeeded");
+        assert!(body["data"][0]["last_success"].is_string());
+
+        let (status, body) = send(&app, "POST", "/api/admin/scheduler/nope/run").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], "NotFound");
+    }
+}
@@ -0,0 +1,214 @@
+use axum::extract::rejection::{JsonRejection, PathRejection};
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{middleware, Json, Router};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::coordination::{self, arrive, count_down_latch, create, wait};
+use crate::db::Database;
+use crate::models::{CountdownKind, ResponseCountdown};
+use crate::time::parse_duration;
+
+use super::error::ApiError;
+use super::middleware::mw_require_store_v1;
+use super::v1::{Envelope, Result};
+
+pub fn routes(db: Database) -> Router {
+    Router::new()
+        .route("/v1/barriers", post(create_barrier))
+        .route("/v1/barriers/:name", get(get_barrier))
+        .route("/v1/barriers/:name/arrive", post(arrive_barrier))
+        .route("/v1/latches", post(create_latch))
+        .route("/v1/latches/:name", get(get_latch))
+        .route("/v1/latches/:name/count_down", post(count_down))
+        .route("/v1/latches/:name/wait", get(wait_latch))
+        .route_layer(middleware::from_fn_with_state(db.clone(), mw_require_store_v1))
+        .with_state(db)
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(super) struct NewBarrier {
+    #[schema(example = "nightly-shards")]
+    name: String,
+    /// Participants released together once the last one arrives.
+    #[schema(minimum = 1)]
+    parties: u32,
+    /// Time left to the participants to arrive, `<n>s|m|h`.
+    #[schema(example = "10m")]
+    timeout: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(super) struct NewLatch {
+    #[schema(example = "nightly-import")]
+    name: String,
+    /// Count downs needed to release the waiters.
+    #[schema(minimum = 1)]
+    count: u32,
+    /// Time left to count down to zero, `<n>s|m|h`.
+    #[schema(example = "1h")]
+    timeout: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/barriers",
+    tag = "coordination",
+    request_body = NewBarrier,
+    responses(
+        (status = 201, description = "Barrier created", body = CountdownEnvelope),
+        (status = 400, description = "Invalid barrier", body = ErrorEnvelope),
+        (status = 409, description = "A barrier with this name is still waiting", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn create_barrier(
+    State(db): State<Database>,
+    payload: core::result::Result<Json<NewBarrier>, JsonRejection>,
+) -> Result<Response> {
+    let Json(payload) = payload?;
+
+    let barrier = create(&db, CountdownKind::Barrier, &payload.name, payload.parties, timeout(&payload.timeout)?).await?;
+
+    created(format!("/v1/barriers/{}", barrier.name), barrier)
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/barriers/{name}",
+    tag = "coordination",
+    params(("name" = String, Path, description = "Barrier name")),
+    responses(
+        (status = 200, description = "Barrier found", body = CountdownEnvelope),
+        (status = 404, description = "Barrier not found", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn get_barrier(
+    State(db): State<Database>,
+    name: core::result::Result<Path<String>, PathRejection>,
+) -> Result<Json<Envelope<ResponseCountdown>>> {
+    let Path(name) = name?;
+
+    Ok(Json(Envelope::new(coordination::get(&db, CountdownKind::Barrier, &name).await?)))
+}
+
+/// Registers a participant and blocks until the last one has arrived.
+#[utoipa::path(
+    post,
+    path = "/v1/barriers/{name}/arrive",
+    tag = "coordination",
+    params(("name" = String, Path, description = "Barrier name")),
+    responses(
+        (status = 200, description = "Every participant arrived", body = CountdownEnvelope),
+        (status = 404, description = "Barrier not found", body = ErrorEnvelope),
+        (status = 409, description = "Barrier already released or timed out", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn arrive_barrier(
+    State(db): State<Database>,
+    name: core::result::Result<Path<String>, PathRejection>,
+) -> Result<Json<Envelope<ResponseCountdown>>> {
+    let Path(name) = name?;
+
+    Ok(Json(Envelope::new(arrive(&db, &name).await?)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/latches",
+    tag = "coordination",
+    request_body = NewLatch,
+    responses(
+        (status = 201, description = "Latch created", body = CountdownEnvelope),
+        (status = 400, description = "Invalid latch", body = ErrorEnvelope),
+        (status = 409, description = "A latch with this name is still waiting", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn create_latch(
+    State(db): State<Database>,
+    payload: core::result::Result<Json<NewLatch>, JsonRejection>,
+) -> Result<Response> {
+    let Json(payload) = payload?;
+
+    let latch = create(&db, CountdownKind::Latch, &payload.name, payload.count, timeout(&payload.timeout)?).await?;
+
+    created(format!("/v1/latches/{}", latch.name), latch)
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/latches/{name}",
+    tag = "coordination",
+    params(("name" = String, Path, description = "Latch name")),
+    responses(
+        (status = 200, description = "Latch found", body = CountdownEnvelope),
+        (status = 404, description = "Latch not found", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn get_latch(
+    State(db): State<Database>,
+    name: core::result::Result<Path<String>, PathRejection>,
+) -> Result<Json<Envelope<ResponseCountdown>>> {
+    let Path(name) = name?;
+
+    Ok(Json(Envelope::new(coordination::get(&db, CountdownKind::Latch, &name).await?)))
+}
+
+/// Counting down a released latch does nothing.
+#[utoipa::path(
+    post,
+    path = "/v1/latches/{name}/count_down",
+    tag = "coordination",
+    params(("name" = String, Path, description = "Latch name")),
+    responses(
+        (status = 200, description = "Latch counted down", body = CountdownEnvelope),
+        (status = 404, description = "Latch not found", body = ErrorEnvelope),
+        (status = 409, description = "Latch timed out", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn count_down(
+    State(db): State<Database>,
+    name: core::result::Result<Path<String>, PathRejection>,
+) -> Result<Json<Envelope<ResponseCountdown>>> {
+    let Path(name) = name?;
+
+    Ok(Json(Envelope::new(count_down_latch(&db, &name).await?)))
+}
+
+/// Blocks until the latch is counted down to zero.
+#[utoipa::path(
+    get,
+    path = "/v1/latches/{name}/wait",
+    tag = "coordination",
+    params(("name" = String, Path, description = "Latch name")),
+    responses(
+        (status = 200, description = "Latch released", body = CountdownEnvelope),
+        (status = 404, description = "Latch not found", body = ErrorEnvelope),
+        (status = 409, description = "Latch timed out", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn wait_latch(
+    State(db): State<Database>,
+    name: core::result::Result<Path<String>, PathRejection>,
+) -> Result<Json<Envelope<ResponseCountdown>>> {
+    let Path(name) = name?;
+
+    Ok(Json(Envelope::new(wait(&db, CountdownKind::Latch, &name).await?)))
+}
+
+fn timeout(timeout: &str) -> core::result::Result<u64, ApiError> {
+    parse_duration(timeout).map_err(|_| ApiError::BadRequest("Invalid timeout format".to_string()))
+}
+
+fn created(location: String, countdown: ResponseCountdown) -> Result<Response> {
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(Envelope::new(countdown))).into_response())
+}
@@ -0,0 +1,217 @@
+use std::convert::Infallible;
+
+use axum::extract::rejection::{JsonRejection, PathRejection};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{get, post};
+use axum::{middleware, Json, Router};
+use serde::{Deserialize, Serialize};
+use tokio_stream::{Stream, StreamExt};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::elections::{campaign, leader, observe, proclaim, resign};
+use crate::models::ResponseLeader;
+use crate::time::parse_duration;
+
+use super::error::ApiError;
+use super::middleware::mw_require_store_v1;
+use super::v1::{Envelope, Result};
+
+/// Named elections, the leader keeps its lease with the lock heartbeat.
+pub fn routes(db: Database) -> Router {
+    Router::new()
+        .route("/v1/elections/:name/campaign", post(campaign_election))
+        .route("/v1/elections/:name/leader", get(election_leader))
+        .route("/v1/elections/:name/proclaim", post(proclaim_election))
+        .route("/v1/elections/:name/resign", post(resign_election))
+        .route("/v1/elections/:name/observe", get(observe_election))
+        .route_layer(middleware::from_fn_with_state(db.clone(), mw_require_store_v1))
+        .with_state(db)
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(super) struct Campaign {
+    /// Published to observers while this candidate leads, e.g. its address.
+    #[schema(example = "10.0.3.17:8080")]
+    value: String,
+    /// Lease of the leadership, `<n>s|m|h`.
+    #[schema(example = "30s")]
+    lease: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(super) struct Proclaim {
+    lock_id: Uuid,
+    #[schema(example = "10.0.3.17:9090")]
+    value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(super) struct Resign {
+    lock_id: Uuid,
+}
+
+/// Blocks until this candidate is elected. Closing the connection withdraws the candidacy.
+#[utoipa::path(
+    post,
+    path = "/v1/elections/{name}/campaign",
+    tag = "elections",
+    params(("name" = String, Path, description = "Election name")),
+    request_body = Campaign,
+    responses(
+        (status = 200, description = "Elected", body = LeaderEnvelope),
+        (status = 400, description = "Invalid name or lease", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn campaign_election(
+    State(db): State<Database>,
+    name: core::result::Result<Path<String>, PathRejection>,
+    payload: core::result::Result<Json<Campaign>, JsonRejection>,
+) -> Result<Json<Envelope<ResponseLeader>>> {
+    let Path(name) = name?;
+    let Json(payload) = payload?;
+    let lease = parse_duration(&payload.lease).map_err(|_| ApiError::BadRequest("Invalid lease format".to_string()))?;
+
+    let leader = campaign(&db, &name, payload.value, lease).await?;
+
+    Ok(Json(Envelope::new(leader)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/elections/{name}/leader",
+    tag = "elections",
+    params(("name" = String, Path, description = "Election name")),
+    responses(
+        (status = 200, description = "Current leader", body = LeaderEnvelope),
+        (status = 400, description = "Invalid name", body = ErrorEnvelope),
+        (status = 404, description = "No leader", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn election_leader(
+    State(db): State<Database>,
+    name: core::result::Result<Path<String>, PathRejection>,
+) -> Result<Json<Envelope<ResponseLeader>>> {
+    let Path(name) = name?;
+
+    Ok(Json(Envelope::new(leader(&db, &name).await?)))
+}
+
+/// Changes the leader value without a new election.
+#[utoipa::path(
+    post,
+    path = "/v1/elections/{name}/proclaim",
+    tag = "elections",
+    params(("name" = String, Path, description = "Election name")),
+    request_body = Proclaim,
+    responses(
+        (status = 200, description = "Value proclaimed", body = LeaderEnvelope),
+        (status = 400, description = "Invalid name", body = ErrorEnvelope),
+        (status = 409, description = "The lock doesn't lead the election", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn proclaim_election(
+    State(db): State<Database>,
+    name: core::result::Result<Path<String>, PathRejection>,
+    payload: core::result::Result<Json<Proclaim>, JsonRejection>,
+) -> Result<Json<Envelope<ResponseLeader>>> {
+    let Path(name) = name?;
+    let Json(payload) = payload?;
+
+    let leader = proclaim(&db, &name, &payload.lock_id.to_string(), payload.value).await?;
+
+    Ok(Json(Envelope::new(leader)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/elections/{name}/resign",
+    tag = "elections",
+    params(("name" = String, Path, description = "Election name")),
+    request_body = Resign,
+    responses(
+        (status = 204, description = "Leadership given up"),
+        (status = 400, description = "Invalid name", body = ErrorEnvelope),
+        (status = 409, description = "The lock doesn't lead the election", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn resign_election(
+    State(db): State<Database>,
+    name: core::result::Result<Path<String>, PathRejection>,
+    payload: core::result::Result<Json<Resign>, JsonRejection>,
+) -> Result<StatusCode> {
+    let Path(name) = name?;
+    let Json(payload) = payload?;
+
+    resign(&db, &name, &payload.lock_id.to_string()).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// One `leader` event with the current leader, then one per new leader or proclaimed value.
+async fn observe_election(
+    State(db): State<Database>,
+    name: core::result::Result<Path<String>, PathRejection>,
+) -> Result<Sse<impl Stream<Item = core::result::Result<Event, Infallible>>>> {
+    let Path(name) = name?;
+
+    let leaders = observe(&db, &name).await?.map(|leader| {
+        Ok(Event::default()
+            .event("leader")
+            .data(serde_json::to_string(&leader).unwrap_or_default()))
+    });
+
+    Ok(Sse::new(leaders).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn send(app: &Router, uri: &str, body: Value) -> (StatusCode, Value) {
+        let req = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        let status = res.status();
+        let bytes = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_campaign_and_resign() {
+        let app = routes(Database::in_memory());
+
+        let (status, body) = send(&app, "/v1/elections/billing/campaign", json!({ "value": "a", "lease": "30s" })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["value"], "a");
+        let lock_id = body["data"]["lock_id"].as_str().unwrap().to_string();
+
+        let other = Uuid::now_v7();
+        let (status, body) = send(&app, "/v1/elections/billing/resign", json!({ "lock_id": other })).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(body["error"]["message"].as_str().unwrap().contains("doesn't lead"));
+
+        let (status, _) = send(&app, "/v1/elections/billing/resign", json!({ "lock_id": lock_id })).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let req = Request::builder().uri("/v1/elections/billing/leader").body(Body::empty()).unwrap();
+        assert_eq!(app.clone().oneshot(req).await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -1,13 +1,14 @@
-use axum::extract::rejection::JsonRejection;
-use axum::http::StatusCode;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::http::{header, StatusCode};
 use axum::response::IntoResponse;
+use chrono::{DateTime, Utc};
 use derive_more::From;
 use serde::Serialize;
 use serde_with::{DisplayFromStr, serde_as};
 use strum_macros::Display;
 use tracing::error;
 
-use crate::db;
+use crate::{coordination, db, elections, scheduler};
 use crate::rest_api::middleware;
 use crate::rest_api::routes::AppJson;
 
@@ -16,8 +17,19 @@ pub type Result<T> = core::result::Result<T, ApiError>;
 #[derive(Debug, Display)]
 pub enum ApiError {
     JsonExtractorRejection(JsonRejection),
+    QueryExtractorRejection(QueryRejection),
+    PathExtractorRejection(PathRejection),
     BadRequest(String),
+    NotFound(String),
+    Conflict(String),
+    /// A run with the same dedupe key completed within its window.
+    AlreadyDone { process_id: String, completed_at: DateTime<Utc> },
+    /// The last run of the process ended less than its cooldown ago.
+    CoolingDown { retry_after: u64 },
     ProcessExist(String),
+    ServiceUnavailable(String),
+    /// Storage or driver failure, its detail is only logged.
+    Internal(String),
     CtxExt(middleware::CtxExtError),
     ReqParts(middleware::RequestInfoError),
 }
@@ -59,8 +71,13 @@ pub enum Error {
     #[from]
     SerdeJson(#[serde_as(as = "DisplayFromStr")] serde_json::Error),
 
-    #[from]
-    SurrealDB(surrealdb::Error),
+    SurrealDB(Box<surrealdb::Error>),
+}
+
+impl From<surrealdb::Error> for Error {
+    fn from(err: surrealdb::Error) -> Self {
+        Self::SurrealDB(Box::new(err))
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -94,21 +111,21 @@ impl Error {
 }
 
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> axum::response::Response {
-        #[derive(Serialize)]
-        struct ErrorResponse {
-            message: String,
-        }
-
-        error!("api error: {:?}", &self);
-
-        let (status, message) = match self {
+impl ApiError {
+    /// Status code and client-facing message, shared by the legacy and v1 error bodies.
+    pub(super) fn status_and_message(self) -> (StatusCode, String) {
+        match self {
             ApiError::JsonExtractorRejection(rejection) => {
                 // This error is caused by bad user input so don't log it
 
                 (rejection.status(), rejection.body_text())
             }
+            ApiError::QueryExtractorRejection(rejection) => {
+                (rejection.status(), rejection.body_text())
+            }
+            ApiError::PathExtractorRejection(rejection) => {
+                (rejection.status(), rejection.body_text())
+            }
             // AppError::TimeError(err) => {
             //     // Because `TraceLayer` wraps each request in a span that contains the request
             //     // method, uri, etc we don't need to include those details here
@@ -126,6 +143,21 @@ impl IntoResponse for ApiError {
             ApiError::BadRequest(e) => {
                 (StatusCode::BAD_REQUEST, e.to_string())
             }
+            ApiError::NotFound(e) => {
+                (StatusCode::NOT_FOUND, e.to_string())
+            }
+            ApiError::Conflict(e) => {
+                (StatusCode::CONFLICT, e.to_string())
+            }
+            ApiError::AlreadyDone { process_id, completed_at } => {
+                (StatusCode::CONFLICT, format!("Already done by {process_id}, completed at {completed_at}"))
+            }
+            ApiError::CoolingDown { retry_after } => {
+                (StatusCode::TOO_MANY_REQUESTS, format!("Process is cooling down, retry in {retry_after}s"))
+            }
+            ApiError::ServiceUnavailable(e) => {
+                (StatusCode::SERVICE_UNAVAILABLE, e.to_string())
+            }
 
             _ => {
                 (
@@ -133,9 +165,31 @@ impl IntoResponse for ApiError {
                     "Something went wrong".to_owned(),
                 )
             }
-        };
+        }
+    }
+
+    /// `Retry-After` header of the response, in seconds.
+    pub(super) fn retry_after(&self) -> Option<[(header::HeaderName, String); 1]> {
+        match self {
+            ApiError::CoolingDown { retry_after } => Some([(header::RETRY_AFTER, retry_after.to_string())]),
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> axum::response::Response {
+        #[derive(Serialize)]
+        struct ErrorResponse {
+            message: String,
+        }
+
+        error!("api error: {:?}", &self);
 
-        (status, AppJson(ErrorResponse { message })).into_response()
+        let retry_after = self.retry_after();
+        let (status, message) = self.status_and_message();
+
+        (status, retry_after, AppJson(ErrorResponse { message })).into_response()
     }
 
     // let mut response = StatusCode::INTERNAL_SERVER_ERROR.into_response();
@@ -145,8 +199,93 @@ impl IntoResponse for ApiError {
     // response
 }
 
+impl From<db::error::Error> for ApiError {
+    fn from(err: db::error::Error) -> Self {
+        match err {
+            db::error::Error::StoreUnavailable => {
+                ApiError::ServiceUnavailable("Storage is unavailable".to_string())
+            }
+            db::error::Error::RecordNotFound => {
+                ApiError::NotFound("Process not found".to_string())
+            }
+            db::error::Error::InvalidStatus(e) => ApiError::BadRequest(e),
+            db::error::Error::Reserved(e) => ApiError::BadRequest(e),
+            db::error::Error::AlreadyDone { process_id, completed_at } => ApiError::AlreadyDone {
+                process_id,
+                completed_at: DateTime::from_timestamp(completed_at as i64, 0).unwrap_or_default(),
+            },
+            db::error::Error::CoolingDown { retry_after } => ApiError::CoolingDown { retry_after },
+            db::error::Error::ProcessExist => ApiError::ProcessExist("Process already exists".to_string()),
+            e => ApiError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<elections::error::Error> for ApiError {
+    fn from(err: elections::error::Error) -> Self {
+        match err {
+            elections::error::Error::InvalidName(e) => ApiError::BadRequest(e),
+            elections::error::Error::NoLeader(e) => ApiError::NotFound(e),
+            elections::error::Error::NotLeader(e) => ApiError::Conflict(e),
+            elections::error::Error::DB(e) => e.into(),
+            elections::error::Error::Time(e) => ApiError::Internal(format!("{e:?}")),
+        }
+    }
+}
+
+impl From<coordination::error::Error> for ApiError {
+    fn from(err: coordination::error::Error) -> Self {
+        match err {
+            coordination::error::Error::InvalidName(e) => ApiError::BadRequest(e),
+            coordination::error::Error::InvalidTotal(e) => ApiError::BadRequest(e),
+            coordination::error::Error::NotFound(e) => ApiError::NotFound(e),
+            coordination::error::Error::Exists(e) => ApiError::Conflict(e),
+            coordination::error::Error::Released(e) => ApiError::Conflict(e),
+            coordination::error::Error::TimedOut(e) => ApiError::Conflict(e),
+            coordination::error::Error::DB(e) => e.into(),
+            coordination::error::Error::Time(e) => ApiError::Internal(format!("{e:?}")),
+        }
+    }
+}
+
+impl From<scheduler::error::Error> for ApiError {
+    fn from(err: scheduler::error::Error) -> Self {
+        match err {
+            scheduler::error::Error::UnknownTask(name) => ApiError::NotFound(format!("Unknown task {name}")),
+            scheduler::error::Error::TaskRunning(name) => ApiError::Conflict(format!("Task {name} is already running")),
+            scheduler::error::Error::DB(e) => e.into(),
+            e => ApiError::Internal(e.to_string()),
+        }
+    }
+}
+
 impl From<JsonRejection> for ApiError {
     fn from(rejection: JsonRejection) -> Self {
         Self::JsonExtractorRejection(rejection)
     }
-}
\ No newline at end of file
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        Self::PathExtractorRejection(rejection)
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::QueryExtractorRejection(rejection)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_failures_are_not_exposed() {
+        let (status, message) = ApiError::from(db::error::Error::Sqlite("disk I/O error".to_string())).status_and_message();
+        assert_eq!((status, message.as_str()), (StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong"));
+
+        let (status, _) = ApiError::from(db::error::Error::StoreUnavailable).status_and_message();
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
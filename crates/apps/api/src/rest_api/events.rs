@@ -0,0 +1,135 @@
+use std::convert::Infallible;
+
+use axum::extract::rejection::PathRejection;
+use axum::extract::{Path, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::get;
+use axum::{middleware, Router};
+use serde::Deserialize;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use uuid::Uuid;
+
+use crate::db::events::watch_process;
+use crate::db::Database;
+use crate::models::Process;
+
+use super::error::Result;
+use super::middleware::{mw_require_store, mw_require_store_v1};
+use super::routes::AppQuery;
+use super::v1;
+
+pub fn routes(db: Database) -> Router {
+    let v1 = Router::new()
+        .route("/v1/locks/:lock_id/events", get(v1_lock_events))
+        .route_layer(middleware::from_fn_with_state(db.clone(), mw_require_store_v1));
+
+    Router::new()
+        .route("/api/locks/events", get(locks_events))
+        .route("/api/locks/:lock_id/events", get(lock_events))
+        .route_layer(middleware::from_fn_with_state(db.clone(), mw_require_store))
+        .merge(v1)
+        .with_state(db)
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct EventsFilter {
+    app: Option<String>,
+    process: Option<String>,
+}
+
+impl EventsFilter {
+    fn matches(&self, p: &Process) -> bool {
+        self.app.as_deref().is_none_or(|app| p.app == app)
+            && self.process.as_deref().is_none_or(|process| p.process_name == process)
+    }
+}
+
+/// One `status` event per transition of a single lock: the current state
+/// first, the stream ends once the lock is finished.
+async fn lock_events(
+    State(db): State<Database>,
+    Path(lock_id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = core::result::Result<Event, Infallible>>>> {
+    let changes = watch_process(&db, &lock_id.to_string()).await?;
+
+    Ok(Sse::new(changes.map(|p| Ok(status_event(&p)))).keep_alive(KeepAlive::default()))
+}
+
+/// Same stream as `lock_events`, failing with the v1 error envelope.
+async fn v1_lock_events(
+    State(db): State<Database>,
+    lock_id: core::result::Result<Path<Uuid>, PathRejection>,
+) -> v1::Result<Sse<impl Stream<Item = core::result::Result<Event, Infallible>>>> {
+    let Path(lock_id) = lock_id?;
+    let changes = watch_process(&db, &lock_id.to_string()).await?;
+
+    Ok(Sse::new(changes.map(|p| Ok(status_event(&p)))).keep_alive(KeepAlive::default()))
+}
+
+/// `status` events for every lock matching `app` / `process`, and a `lagged`
+/// event with the number of skipped events when the client falls behind.
+async fn locks_events(
+    State(db): State<Database>,
+    AppQuery(filter): AppQuery<EventsFilter>,
+) -> Sse<impl Stream<Item = core::result::Result<Event, Infallible>>> {
+    let events = BroadcastStream::new(db.subscribe()).filter_map(move |event| match event {
+        Ok(p) if filter.matches(&p) => Some(Ok(status_event(&p))),
+        Ok(_) => None,
+        Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+            Some(Ok(Event::default().event("lagged").data(skipped.to_string())))
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+fn status_event(p: &Process) -> Event {
+    Event::default()
+        .event("status")
+        .id(format!("{}:{}", p.process_id, p.updated_at))
+        .data(serde_json::to_string(&p.to_response()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{Body, BodyDataStream};
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::db::repository::{acquire_process, update_process_status};
+    use crate::models::OperationStatus;
+
+    async fn next_event(body: &mut BodyDataStream) -> String {
+        String::from_utf8(body.next().await.unwrap().unwrap().to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_lock_events_until_finished() {
+        let db = Database::in_memory();
+        let id = acquire_process(&db, "billing".to_string(), "invoices".to_string(), 60)
+            .await
+            .unwrap();
+
+        let req = Request::builder()
+            .uri(format!("/api/locks/{id}/events"))
+            .body(Body::empty())
+            .unwrap();
+        let res = routes(db.clone()).oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let mut body = res.into_body().into_data_stream();
+
+        assert!(next_event(&mut body).await.contains(r#""status":"New""#));
+
+        update_process_status(&db, &id, OperationStatus::InProgress).await.unwrap();
+        assert!(next_event(&mut body).await.contains(r#""status":"InProgress""#));
+
+        update_process_status(&db, &id, OperationStatus::Completed).await.unwrap();
+        assert!(next_event(&mut body).await.contains(r#""status":"Completed""#));
+
+        assert!(body.next().await.is_none());
+    }
+}
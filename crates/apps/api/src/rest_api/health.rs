@@ -0,0 +1,53 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::json;
+
+use crate::db::Database;
+
+pub fn routes(db: Database) -> Router {
+    Router::new()
+        .route("/health/live", get(live))
+        .route("/health/ready", get(ready))
+        .with_state(db)
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses((status = 200, description = "Process is running"))
+)]
+pub(super) async fn live() -> Response {
+    Json(json!({ "status": "ok" })).into_response()
+}
+
+// Ready only while the store is connected, so load balancers stop routing
+// traffic to an instance that would answer every request with 503.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Storage is connected"),
+        (status = 503, description = "Storage is (re)connecting"),
+    )
+)]
+pub(super) async fn ready(State(db): State<Database>) -> Response {
+    let health = db.health();
+
+    let (status_code, status) = if db.is_available() {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+
+    let body = Json(json!({
+        "status": status,
+        "store": health,
+    }));
+
+    (status_code, body).into_response()
+}
@@ -1,9 +1,9 @@
 use axum::body::Body;
-use axum::extract::FromRequestParts;
+use axum::extract::{FromRequestParts, State};
 use std::sync::Arc;
 
 use axum::http::request::Parts;
-use axum::http::{Method, Request, Uri};
+use axum::http::{header, HeaderValue, Method, Request, Uri};
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
 use axum::{async_trait, Json};
@@ -14,8 +14,10 @@ use serde_json::{json, to_value};
 use tracing::{debug, info};
 use uuid::Uuid;
 
+use crate::db::Database;
 use super::error::Result;
 use super::error::{ApiError, Error};
+use super::v1;
 use lib_core::ctx::Ctx;
 
 #[derive(Debug, Clone)]
@@ -79,6 +81,42 @@ pub async fn log_result(req: Request<Body>, next: Next) -> Result<Response> {
     Ok(next.run(req).await)
 }
 
+/// Marks responses of the legacy RPC-style routes as deprecated in favour of `/v1/locks`.
+pub async fn mw_deprecated(mut res: Response) -> Response {
+    let headers = res.headers_mut();
+    headers.insert("Deprecation", HeaderValue::from_static("true"));
+    headers.insert(
+        header::LINK,
+        HeaderValue::from_static("</v1/locks>; rel=\"successor-version\""),
+    );
+
+    res
+}
+
+/// Rejects requests with 503 while the store is (re)connecting.
+pub async fn mw_require_store(
+    State(db): State<Database>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response> {
+    if !db.is_available() {
+        return Err(ApiError::ServiceUnavailable(
+            "Storage is unavailable".to_string(),
+        ));
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// [`mw_require_store`] for the `/v1` routes, with the v1 error body.
+pub async fn mw_require_store_v1(
+    State(db): State<Database>,
+    req: Request<Body>,
+    next: Next,
+) -> v1::Result<Response> {
+    Ok(mw_require_store(State(db), req, next).await?)
+}
+
 async fn _ctx_resolve() -> CtxExtResult {
     let ctx = Ctx::default();
 
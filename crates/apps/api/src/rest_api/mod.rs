@@ -1,6 +1,15 @@
+mod admin;
+mod coordination;
+mod elections;
 pub mod error;
+mod events;
+mod health;
 mod routes;
+mod session;
 pub mod server;
 mod middleware;
+mod openapi;
 mod params;
+mod v1;
+mod webhooks;
 
@@ -0,0 +1,176 @@
+use axum::routing::get;
+use axum::{Json, Router};
+use utoipa::OpenApi;
+
+use crate::db::repository::SortField;
+use crate::models::{
+    CountdownStatus, DeadLetter, OperationStatus, ResponseCountdown, ResponseLeader, ResponseProcess, ResponseWebhook,
+    WebhookEvent,
+};
+use crate::scheduler::{Action, Outcome, PlannedChange, TaskRun, TaskStatus};
+
+use super::params::{Heartbeat, NewProcess, UpdateProcess};
+use super::v1::{
+    CountdownEnvelope, DeadLetterListEnvelope, DoneRun, ErrorDetail, ErrorEnvelope, LeaderEnvelope, LockEnvelope,
+    LockListEnvelope, Meta, PlanEnvelope, SchedulerEnvelope, TaskRunEnvelope, WebhookEnvelope, WebhookListEnvelope,
+};
+use super::coordination::{NewBarrier, NewLatch};
+use super::elections::{Campaign, Proclaim, Resign};
+use super::webhooks::NewWebhook;
+use super::{admin, coordination, elections, health, v1, webhooks};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "flowlocker", description = "Locks that keep a task from running more than once."),
+    paths(
+        v1::create_lock,
+        v1::get_lock,
+        v1::list_locks,
+        v1::update_lock,
+        v1::release_lock,
+        v1::heartbeat_lock,
+        webhooks::subscribe,
+        webhooks::list_webhooks,
+        webhooks::unsubscribe,
+        webhooks::list_dead_letters,
+        elections::campaign_election,
+        elections::election_leader,
+        elections::proclaim_election,
+        elections::resign_election,
+        coordination::create_barrier,
+        coordination::get_barrier,
+        coordination::arrive_barrier,
+        coordination::create_latch,
+        coordination::get_latch,
+        coordination::count_down,
+        coordination::wait_latch,
+        admin::scheduler_status,
+        admin::run_task,
+        admin::dry_run,
+        health::live,
+        health::ready,
+    ),
+    components(schemas(
+        NewProcess,
+        UpdateProcess,
+        Heartbeat,
+        ResponseProcess,
+        OperationStatus,
+        SortField,
+        LockEnvelope,
+        LockListEnvelope,
+        NewWebhook,
+        ResponseWebhook,
+        WebhookEvent,
+        DeadLetter,
+        WebhookEnvelope,
+        WebhookListEnvelope,
+        DeadLetterListEnvelope,
+        Campaign,
+        Proclaim,
+        Resign,
+        ResponseLeader,
+        LeaderEnvelope,
+        NewBarrier,
+        NewLatch,
+        ResponseCountdown,
+        CountdownStatus,
+        CountdownEnvelope,
+        TaskStatus,
+        TaskRun,
+        Outcome,
+        SchedulerEnvelope,
+        TaskRunEnvelope,
+        PlannedChange,
+        Action,
+        PlanEnvelope,
+        Meta,
+        ErrorEnvelope,
+        ErrorDetail,
+        DoneRun,
+    )),
+    tags(
+        (name = "locks", description = "Acquire, inspect and release locks"),
+        (name = "webhooks", description = "Signed HTTP callbacks on lock events"),
+        (name = "elections", description = "Named leader elections built on locks"),
+        (name = "coordination", description = "Barriers and countdown latches"),
+        (name = "admin", description = "Scheduler introspection for operators"),
+        (name = "health", description = "Liveness and readiness probes"),
+    )
+)]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new().route("/openapi.json", get(|| async { Json(ApiDoc::openapi()) }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode};
+    use tower::ServiceExt;
+    use utoipa::openapi::PathItemType;
+
+    use super::*;
+    use crate::db::cooldowns::CooldownPolicy;
+    use crate::db::Database;
+    use crate::scheduler::Registry;
+
+    const SPEC_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+    /// The committed spec is what consumers generate clients from. Regenerate it with
+    /// `UPDATE_OPENAPI=1 cargo test -p flowlocker openapi` after changing the API.
+    #[test]
+    fn test_spec_matches_committed_file() {
+        let spec = ApiDoc::openapi().to_pretty_json().unwrap();
+
+        if std::env::var("UPDATE_OPENAPI").is_ok() {
+            std::fs::write(SPEC_PATH, format!("{spec}\n")).unwrap();
+        }
+
+        let committed = std::fs::read_to_string(SPEC_PATH).unwrap_or_default();
+        assert_eq!(
+            committed.trim_end(),
+            spec,
+            "openapi.json is out of date, regenerate it with UPDATE_OPENAPI=1"
+        );
+    }
+
+    /// Every documented operation must be served by the router.
+    #[tokio::test]
+    async fn test_documented_paths_are_routed() {
+        let db = Database::in_memory();
+        let app = Router::new()
+            .merge(v1::routes(db.clone(), CooldownPolicy::default()))
+            .merge(webhooks::routes(db.clone()))
+            .merge(elections::routes(db.clone()))
+            .merge(coordination::routes(db.clone()))
+            .merge(admin::routes(Registry::new(std::time::Duration::from_secs(30))))
+            .merge(health::routes(db));
+
+        for (path, item) in ApiDoc::openapi().paths.paths {
+            let uri = path
+                .replace("{lock_id}", &uuid::Uuid::now_v7().to_string())
+                .replace("{webhook_id}", &uuid::Uuid::now_v7().to_string())
+                .replace("{name}", "routed");
+
+            for operation in item.operations.keys() {
+                let method = match operation {
+                    PathItemType::Get => Method::GET,
+                    PathItemType::Post => Method::POST,
+                    PathItemType::Patch => Method::PATCH,
+                    PathItemType::Delete => Method::DELETE,
+                    _ => panic!("unexpected operation on {path}"),
+                };
+
+                let req = Request::builder().method(method.clone()).uri(&uri).body(Body::empty()).unwrap();
+                let status = app.clone().oneshot(req).await.unwrap().status();
+
+                assert_ne!(status, StatusCode::METHOD_NOT_ALLOWED, "{method} {path} is not routed");
+                if !path.contains('{') {
+                    assert_ne!(status, StatusCode::NOT_FOUND, "{method} {path} is not routed");
+                }
+            }
+        }
+    }
+}
@@ -1,59 +1,134 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::IntoParams;
+use lib_query_builder::builder::Order;
+use crate::db::repository::{Cursor, ProcessFilter, SortField, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
 use crate::models::OperationStatus;
 use crate::rest_api::error::ApiError;
+use crate::time::parse_duration;
+
+pub(super) use lib_models::lock::{Heartbeat, NewProcess, UpdateProcess};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub(super) struct NewProcess {
-    pub(crate) app: String,
-    pub(crate) process: String,
-    eta: String,
-}
 
 
 #[derive(Debug, Serialize, Deserialize)]
 pub(super) struct GetProcess {}
 
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub(super) struct GetProcesses {
     pub(crate) app: Option<String>,
     pub(crate) process: Option<String>,
-    pub(crate) status: Option<OperationStatus>,
+    /// One or more comma separated statuses.
+    #[serde(default, deserialize_with = "comma_separated_statuses")]
+    #[param(value_type = Option<String>, example = "New,InProgress")]
+    pub(crate) status: Vec<OperationStatus>,
+    pub(crate) created_after: Option<DateTime<Utc>>,
+    pub(crate) created_before: Option<DateTime<Utc>>,
+    pub(crate) order_by: Option<SortField>,
+    /// `asc` (default) or `desc`.
+    #[param(value_type = Option<String>, pattern = "^(asc|desc)$")]
+    pub(crate) order: Option<Order>,
+    /// `next_cursor` of the previous page.
+    pub(crate) cursor: Option<String>,
+    #[param(minimum = 1, maximum = 1000)]
+    pub(crate) limit: Option<u32>,
+}
+
+impl GetProcesses {
+    pub(crate) fn into_filter(self) -> crate::rest_api::error::Result<ProcessFilter> {
+        let limit = self.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        if limit == 0 || limit > MAX_PAGE_SIZE {
+            return Err(ApiError::BadRequest(format!("limit must be between 1 and {MAX_PAGE_SIZE}")));
+        }
+
+        Ok(ProcessFilter {
+            app: self.app,
+            process_name: self.process,
+            statuses: self.status,
+            created_after: self.created_after.map(|t| t.timestamp().max(0) as u64),
+            created_before: self.created_before.map(|t| t.timestamp().max(0) as u64),
+            sort: self.order_by.unwrap_or_default(),
+            order: self.order.unwrap_or_default(),
+            cursor: self.cursor.as_deref().map(decode_cursor).transpose()?,
+            limit,
+        })
+    }
 }
 
+/// Accepts `status=New,InProgress` as well as a single status.
+fn comma_separated_statuses<'de, D>(deserializer: D) -> Result<Vec<OperationStatus>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<OperationStatus>().map_err(serde::de::Error::custom))
+        .collect()
+}
 
-impl NewProcess {
-    pub(crate) fn eta_to_u64(&self) -> crate::rest_api::error::Result<u64> {
+fn decode_cursor(cursor: &str) -> crate::rest_api::error::Result<Cursor> {
+    Cursor::decode(cursor).ok_or_else(|| ApiError::BadRequest("Invalid cursor".to_string()))
+}
+
+/// Request bodies carrying an `eta` in the `<n>s|m|h` format.
+pub(super) trait Eta {
+    fn eta_to_u64(&self) -> crate::rest_api::error::Result<u64>;
+}
+
+impl Eta for NewProcess {
+    fn eta_to_u64(&self) -> crate::rest_api::error::Result<u64> {
         string_to_duration(self.eta.as_str())
     }
 }
 
+impl Eta for Heartbeat {
+    fn eta_to_u64(&self) -> crate::rest_api::error::Result<u64> {
+        string_to_duration(self.eta.as_str())
+    }
+}
 
-/// string_to_duration accept data in string format
-/// #### Seconds
-/// "60s"
-/// #### Minutes
-/// "5m"
-/// #### Hours
-/// "2h"
-fn string_to_duration(duration: &str) -> crate::rest_api::error::Result<u64> {
-    let len = duration.len();
-    let (value, unit) = duration.split_at(len - 1);
-    match value.parse::<u64>() {
-        Ok(value) => match unit {
-            "s" => Ok(value),
-            "m" => Ok(value * 60),
-            "h" => Ok(value * 60 * 60),
-            _ => Err(ApiError::BadRequest("Invalid ETA format".to_string()))
-        },
-        Err(_) => Err(ApiError::BadRequest("Invalid ETA format".to_string())),
+/// `dedupe_key` and `window` of an acquisition, they go together.
+pub(super) trait Dedupe {
+    fn dedupe(&self) -> crate::rest_api::error::Result<Option<(String, u64)>>;
+}
+
+impl Dedupe for NewProcess {
+    fn dedupe(&self) -> crate::rest_api::error::Result<Option<(String, u64)>> {
+        match (&self.dedupe_key, &self.window) {
+            (None, None) => Ok(None),
+            (Some(key), Some(window)) if !key.is_empty() => {
+                let window = parse_duration(window).map_err(|_| ApiError::BadRequest("Invalid window format".to_string()))?;
+                Ok(Some((key.clone(), window)))
+            }
+            _ => Err(ApiError::BadRequest("dedupe_key and window must be set together".to_string())),
+        }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub(super) struct UpdateProcess {
-    status: OperationStatus,
+/// `cooldown` of an acquisition, `None` leaves it to the policy.
+pub(super) trait Cooldown {
+    fn cooldown(&self) -> crate::rest_api::error::Result<Option<u64>>;
+}
+
+impl Cooldown for NewProcess {
+    fn cooldown(&self) -> crate::rest_api::error::Result<Option<u64>> {
+        self.cooldown
+            .as_deref()
+            .map(|c| parse_duration(c).map_err(|_| ApiError::BadRequest("Invalid cooldown format".to_string())))
+            .transpose()
+    }
 }
+
+fn string_to_duration(duration: &str) -> crate::rest_api::error::Result<u64> {
+    parse_duration(duration).map_err(|_| ApiError::BadRequest("Invalid ETA format".to_string()))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub(super) struct UnlockProcess {}
 
@@ -78,3 +153,36 @@ impl ProcessData for UpdateProcess {
 pub trait ProcessData {
     fn get_status(&self) -> OperationStatus;
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::Query;
+    use axum::http::Uri;
+
+    use super::*;
+
+    #[test]
+    fn test_get_processes_from_query_string() {
+        let uri: Uri = "/api/get_processes_list?app=billing&status=New,InProgress&order=desc&limit=10"
+            .parse()
+            .unwrap();
+        let Query(params) = Query::<GetProcesses>::try_from_uri(&uri).unwrap();
+
+        let filter = params.into_filter().unwrap();
+        assert_eq!(filter.app.as_deref(), Some("billing"));
+        assert_eq!(filter.statuses, vec![OperationStatus::New, OperationStatus::InProgress]);
+        assert_eq!(filter.order, Order::Desc);
+        assert_eq!(filter.limit, 10);
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor {
+            value: 1_700_000_000,
+            process_id: "0190a5b6-8d2e-7c3a-9f1e-2b4c6d8e0f12".to_string(),
+        };
+
+        assert_eq!(decode_cursor(&cursor.encode()).unwrap(), cursor);
+        assert!(decode_cursor("not-a-cursor").is_err());
+    }
+}
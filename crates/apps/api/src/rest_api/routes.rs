@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
-use axum::extract::FromRequest;
+use axum::extract::{FromRef, FromRequest, FromRequestParts};
+use axum::middleware;
 use axum::response::{IntoResponse, Response};
 use axum::{
     extract::{Json, Path, State},
@@ -17,11 +18,13 @@ use tracing::{error, info, instrument};
 use uuid::Uuid;
 
 use super::error::{ApiError, ErrorType, Result};
-use super::params::{GetProcesses, NewProcess, ProcessData, RequestEndpoint, UnlockProcess, UpdateProcess};
-use crate::db::repository::{
-    check_running_processes, create_new_process, get_process_by_id, update_process_status, get_processes,
-};
-use crate::models::{OperationStatus};
+use super::middleware::{mw_deprecated, mw_require_store};
+use super::params::{Cooldown, Dedupe, Eta, GetProcesses, NewProcess, ProcessData, RequestEndpoint, UnlockProcess, UpdateProcess};
+use crate::db;
+use crate::db::cooldowns::CooldownPolicy;
+use crate::db::locks::{self, Acquisition};
+use crate::db::repository::{self, get_process_by_id, get_processes, Cursor};
+use crate::models::OperationStatus;
 
 // Create our own JSON extractor by wrapping `axum::Json`. This makes it easy to override the
 // rejection and provide our own which formats errors to match our application.
@@ -40,21 +43,36 @@ where
     }
 }
 
-pub fn routes(db: Database) -> Router {
+// Same for query strings: `axum::extract::Query` rejects with plain text.
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ApiError))]
+pub struct AppQuery<T>(pub T);
+
+/// State of the routers acquiring locks, their other handlers only take the `Database`.
+#[derive(Clone, FromRef)]
+pub(super) struct LockState {
+    pub(super) db: Database,
+    pub(super) cooldowns: CooldownPolicy,
+}
+
+pub fn routes(db: Database, cooldowns: CooldownPolicy) -> Router {
     Router::new()
         .route("/api/lock_new_process", post(create_new_lock))
         .route("/api/get_locked_process/:lock_id", get(get_locked_process))
         .route("/api/get_processes_list", get(get_processes_list))
         .route("/api/update_process_status/:lock_id", post(set_process_status))
         .route("/api/unlock_process/:lock_id", post(unlock_process))
-        .with_state(db)
+        .route_layer(middleware::from_fn_with_state(db.clone(), mw_require_store))
+        .layer(middleware::map_response(mw_deprecated))
+        .with_state(LockState { db, cooldowns })
 }
 
 async fn create_new_lock(
     State(db): State<Database>,
+    State(cooldowns): State<CooldownPolicy>,
     AppJson(payload): AppJson<NewProcess>,
 ) -> Response {
-    let mut res = _handle_create_new_lock(db, payload).await.into_response();
+    let mut res = _handle_create_new_lock(db, cooldowns, payload).await.into_response();
     res.extensions_mut()
         .insert(Arc::new(RequestEndpoint::StartNewLock));
 
@@ -76,22 +94,20 @@ async fn get_locked_process(
 
 async fn get_processes_list(
     State(db): State<Database>,
-    AppJson(payload): AppJson<GetProcesses>,
+    AppQuery(payload): AppQuery<GetProcesses>,
 ) -> Response {
-    println!("{:?}", payload);
-
-    let res = match get_processes(&db, payload.app, payload.process, payload.status).await {
-        Ok(processes) => {
-            let data = match processes {
-                // Some(p) => p.iter().map(|&r| r.to_response()).collect(),
-                Some(p) => p,
-                _ => Vec::new(),
-            };
+    let filter = match payload.into_filter() {
+        Ok(filter) => filter,
+        Err(e) => return e.into_response(),
+    };
 
+    let res = match get_processes(&db, filter).await {
+        Ok(page) => {
             let body = Json(json!({
                 "result": {
                     "success": true,
-                    "data": data,
+                    "data": page.processes.iter().map(|p| p.to_response()).collect::<Vec<_>>(),
+                    "next_cursor": page.next_cursor.as_ref().map(Cursor::encode),
                 }
             }));
 
@@ -99,7 +115,7 @@ async fn get_processes_list(
         }
         Err(e) => {
             error!("Request completed with the error: {:?}", e);
-            Err(ApiError::BadRequest(e.to_string()))
+            Err(ApiError::from(e))
         }
     };
 
@@ -130,31 +146,12 @@ async fn unlock_process(
 async fn _handle_create_new_lock(
     // ctx: Ctx,
     db: Database,
+    cooldowns: CooldownPolicy,
     payload: NewProcess,
 ) -> Result<Json<Value>> {
     // info!("Request with data {:?}", payload);
 
-    let running_processes = match check_running_processes(&db, &payload.app, &payload.process).await
-    {
-        Ok(ok) => ok,
-        Err(e) => return Err(ApiError::BadRequest(e.to_string())),
-    };
-
-    if let Some(processes) = running_processes {
-        if !processes.is_empty() {
-            return Err(ApiError::from((
-                ErrorType::ProcessExist,
-                String::from("Process already exists"),
-            )));
-        }
-    }
-
-    let etc = payload.eta_to_u64()?;
-
-    let id = match create_new_process(&db, payload.app, payload.process, etc).await {
-        Ok(ok) => ok,
-        Err(e) => return Err(ApiError::BadRequest(e.to_string())),
-    };
+    let id = acquire_lock(&db, &cooldowns, payload).await?;
 
     let body = Json(json!({
         "result": {
@@ -181,7 +178,7 @@ async fn _handle_get_locked_process(db: Database, lock_id: Uuid) -> Result<Json<
         }
         Err(e) => {
             error!("Request completed with the error: {:?}", e);
-            Err(ApiError::BadRequest(e.to_string()))
+            Err(ApiError::from(e))
         }
     }
 }
@@ -191,28 +188,7 @@ async fn _handle_set_process_status<T: ProcessData>(
     id: String,
     data: T,
 ) -> Result<Json<Value>> {
-    if data.get_status() == OperationStatus::Outdated {
-        println!("{}", data.get_status());
-        return Err(ApiError::BadRequest("bad operational status".to_string()));
-    }
-
-    let _p = match get_process_by_id(&db, &id).await {
-        Ok(p) => {
-            if p.status.is_outdated() {
-                return Err(ApiError::BadRequest(
-                    "can't updated Outdated process".to_string(),
-                ));
-            } else {
-                p
-            }
-        }
-        Err(e) => return Err(ApiError::BadRequest(e.to_string())),
-    };
-
-    match update_process_status(&db, &id, data.get_status()).await {
-        Ok(ok) => ok,
-        Err(e) => return Err(ApiError::BadRequest(e.to_string())),
-    };
+    change_lock_status(&db, &id, data.get_status()).await?;
 
     let body = Json(json!({
         "result": {
@@ -222,3 +198,27 @@ async fn _handle_set_process_status<T: ProcessData>(
 
     Ok(body)
 }
+
+/// `payload` taken through [`locks::acquire_lock`], whose cooldown defaults to `cooldowns`.
+pub(super) async fn acquire_lock(db: &Database, cooldowns: &CooldownPolicy, payload: NewProcess) -> Result<String> {
+    let acquisition = Acquisition {
+        eta: payload.eta_to_u64()?,
+        dedupe: payload.dedupe()?,
+        cooldown: payload.cooldown()?,
+        app: payload.app,
+        process: payload.process,
+    };
+
+    match locks::acquire_lock(db, cooldowns, acquisition).await {
+        Ok(id) => Ok(id),
+        Err(db::error::Error::ProcessExist) => Err(ApiError::from((
+            ErrorType::ProcessExist,
+            String::from("Process already exists"),
+        ))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub(super) async fn change_lock_status(db: &Database, id: &str, status: OperationStatus) -> Result<()> {
+    repository::set_process_status(db, id, status).await.map_err(ApiError::from)
+}
@@ -1,39 +1,43 @@
 use axum::{middleware, Router};
 //use signal_hook::iterator::Signals;
 use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
 use tracing::info;
 //use tokio::signal;
+use crate::config::config;
+use crate::db::cooldowns::CooldownPolicy;
 use crate::db::Database;
-//use crate::shutdown_signal;
+use crate::scheduler::Registry;
 
-use super::error::Result;
+use super::{admin, coordination, elections, events, health, openapi, session, v1, webhooks};
 use super::routes::routes;
 use super::middleware::{mw_response_map, mw_ctx_resolver, log_result};
 
-pub async fn new_server(db: Database) -> Result<()> {
+/// Serves until `shutdown` is cancelled, then stops accepting connections and
+/// waits for the open ones.
+pub async fn new_server(db: Database, registry: Registry, shutdown: CancellationToken) -> std::io::Result<()> {
+    let cooldowns = CooldownPolicy::new(config().cooldowns.clone());
     let routes_all = Router::new()
-        .merge(routes(db.clone()))
+        .merge(routes(db.clone(), cooldowns.clone()))
+        .merge(v1::routes(db.clone(), cooldowns.clone()))
+        .merge(webhooks::routes(db.clone()))
+        .merge(events::routes(db.clone()))
+        .merge(elections::routes(db.clone()))
+        .merge(coordination::routes(db.clone()))
+        .merge(session::routes(db.clone(), cooldowns, config().ws_heartbeat_timeout))
+        .merge(admin::routes(registry))
+        .merge(openapi::routes())
+        .merge(health::routes(db.clone()))
         .layer(middleware::map_response(mw_response_map))
         .layer(middleware::from_fn(log_result))
         .layer(middleware::from_fn(mw_ctx_resolver));
 
-    //    let (close_tx, close_rx) = tokio::sync::oneshot::channel();
-
     info!("Starting axum server on port {}", 8050);
 
-    let listener = TcpListener::bind("0.0.0.0:8050").await.unwrap();
+    let listener = TcpListener::bind("0.0.0.0:8050").await?;
     axum::serve(listener, routes_all.into_make_service())
-        .await
-        .unwrap();
-
-//    tokio::spawn(async {
-//        axum::serve(listener, routes_all.into_make_service())
-//            .with_graceful_shutdown(async move {
-//                _ = close_rx.await;
-//            })
-//            .await
-//            .unwrap();
-//    });
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await?;
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,270 @@
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use axum::routing::get;
+use axum::{middleware, Router};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use tokio_stream::StreamExt;
+use tracing::{debug, error, info, instrument};
+
+use crate::db::cooldowns::CooldownPolicy;
+use crate::db::events::watch_process;
+use crate::db::repository::{renew_process, set_process_status};
+use crate::db::Database;
+use crate::models::{OperationStatus, ResponseProcess};
+
+use super::error::Result;
+use super::middleware::mw_require_store;
+use super::params::{Eta, NewProcess};
+use super::routes::{acquire_lock, AppQuery};
+
+#[derive(Clone)]
+struct SessionState {
+    db: Database,
+    cooldowns: CooldownPolicy,
+    heartbeat_timeout: Duration,
+}
+
+/// `GET /api/locks/session?app=..&process=..&eta=..` acquires the lock, then
+/// upgrades to a WebSocket that holds it.
+///
+/// The lock is canceled as soon as the socket closes or nothing (message,
+/// ping or pong) is received for `heartbeat_timeout`. Every `heartbeat`
+/// command moves the lock deadline to `eta` from now.
+pub fn routes(db: Database, cooldowns: CooldownPolicy, heartbeat_timeout: Duration) -> Router {
+    Router::new()
+        .route("/api/locks/session", get(open_session))
+        .route_layer(middleware::from_fn_with_state(db.clone(), mw_require_store))
+        .with_state(SessionState { db, cooldowns, heartbeat_timeout })
+}
+
+/// Sent by the client as JSON text frames.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SessionCommand {
+    Heartbeat,
+    Status { status: OperationStatus },
+    Release,
+}
+
+/// Sent by the server: the lock on every change (the first one right after
+/// the upgrade), and failed commands.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SessionMessage {
+    Lock { lock: ResponseProcess },
+    Error { message: String },
+}
+
+async fn open_session(
+    State(state): State<SessionState>,
+    AppQuery(payload): AppQuery<NewProcess>,
+    ws: WebSocketUpgrade,
+) -> Result<Response> {
+    let eta = payload.eta_to_u64()?;
+    let id = acquire_lock(&state.db, &state.cooldowns, payload).await?;
+
+    let db = state.db.clone();
+    let failed_id = id.clone();
+
+    let res = ws
+        .on_failed_upgrade(move |e| {
+            error!("lock session upgrade failed: {:?}", e);
+            tokio::spawn(async move { cancel_lock(&db, &failed_id).await });
+        })
+        .on_upgrade(move |socket| hold_lock(socket, state, id, eta));
+
+    Ok(res)
+}
+
+#[instrument(skip(socket, state))]
+async fn hold_lock(mut socket: WebSocket, state: SessionState, id: String, eta: u64) {
+    let SessionState { db, heartbeat_timeout, .. } = state;
+
+    let mut changes = match watch_process(&db, &id).await {
+        Ok(changes) => changes,
+        Err(e) => {
+            error!("can't watch lock {}: {:?}", id, e);
+            cancel_lock(&db, &id).await;
+            return;
+        }
+    };
+
+    let mut ping = tokio::time::interval(heartbeat_timeout / 2);
+    let mut last_seen = Instant::now();
+
+    loop {
+        tokio::select! {
+            change = changes.next() => {
+                let Some(p) = change else { break };
+
+                if send(&mut socket, SessionMessage::Lock { lock: p.to_response() }).await.is_err() {
+                    break;
+                }
+
+                // Released by the client or outdated by the cleaner.
+                if p.status.is_finished() {
+                    let _ = socket.close().await;
+                    return;
+                }
+            }
+            msg = socket.recv() => {
+                let msg = match msg {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(msg)) => msg,
+                };
+
+                last_seen = Instant::now();
+
+                if let Message::Text(text) = msg {
+                    if let Err(message) = handle_command(&db, &id, eta, &text).await {
+                        if send(&mut socket, SessionMessage::Error { message }).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            _ = ping.tick() => {
+                if last_seen.elapsed() > heartbeat_timeout {
+                    info!(name = "lock session heartbeat timeout", process_id = %id);
+                    break;
+                }
+
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    info!(name = "lock session closed", process_id = %id);
+    cancel_lock(&db, &id).await;
+}
+
+async fn handle_command(db: &Database, id: &str, eta: u64, text: &str) -> core::result::Result<(), String> {
+    let command: SessionCommand = serde_json::from_str(text).map_err(|e| e.to_string())?;
+
+    let res = match command {
+        SessionCommand::Heartbeat => renew_process(db, id, eta).await,
+        SessionCommand::Status { status } => set_process_status(db, id, status).await,
+        SessionCommand::Release => set_process_status(db, id, OperationStatus::Completed).await,
+    };
+
+    res.map_err(|e| e.to_string())
+}
+
+async fn send(socket: &mut WebSocket, msg: SessionMessage) -> core::result::Result<(), axum::Error> {
+    let text = serde_json::to_string(&msg).unwrap_or_default();
+    socket.send(Message::Text(text)).await
+}
+
+/// The holder is gone: free the lock now instead of waiting for its SLA.
+async fn cancel_lock(db: &Database, id: &str) {
+    if let Err(e) = set_process_status(db, id, OperationStatus::Canceled).await {
+        debug!("can't cancel lock {}: {:?}", id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::SinkExt;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite;
+
+    use super::*;
+    use crate::db::repository::get_process_by_id;
+
+    #[tokio::test]
+    async fn test_lock_canceled_on_disconnect() {
+        let db = Database::in_memory();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = routes(db.clone(), CooldownPolicy::default(), Duration::from_secs(30));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let url = format!("ws://{addr}/api/locks/session?app=billing&process=invoices&eta=60s");
+        let (mut ws, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+
+        let first = ws.next().await.unwrap().unwrap().into_text().unwrap();
+        let first: serde_json::Value = serde_json::from_str(&first).unwrap();
+        assert_eq!(first["lock"]["status"], "New");
+        let id = first["lock"]["process_id"].as_str().unwrap().to_string();
+
+        // A second holder is rejected before the upgrade.
+        let second = tokio_tungstenite::connect_async(url.as_str()).await;
+        assert!(matches!(
+            second,
+            Err(tungstenite::Error::Http(res)) if res.status() == axum::http::StatusCode::LOCKED
+        ));
+
+        ws.send(tungstenite::Message::Text(r#"{"type":"heartbeat"}"#.to_string()))
+            .await
+            .unwrap();
+        drop(ws);
+
+        let mut status = OperationStatus::New;
+        for _ in 0..50 {
+            status = get_process_by_id(&db, &id).await.unwrap().status;
+            if status.is_finished() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(status, OperationStatus::Canceled);
+    }
+
+    #[tokio::test]
+    async fn test_lost_dedupe_races_publish_nothing() {
+        let db = Database::in_memory();
+        let mut events = db.subscribe();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = routes(db.clone(), CooldownPolicy::default(), Duration::from_secs(30));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        // Held until the end, closing them would publish their cancellation.
+        let mut sessions = Vec::new();
+
+        // The race is lost in the store by all but one, a few rounds make it likely.
+        for round in 0..4 {
+            // Connected first, so the upgrade requests reach the server together.
+            let mut streams = Vec::new();
+            for _ in 0..64 {
+                streams.push(tokio::net::TcpStream::connect(addr).await.unwrap());
+            }
+            let racers: Vec<_> = streams
+                .into_iter()
+                .enumerate()
+                .map(|(i, stream)| {
+                    let url = format!(
+                        "ws://{addr}/api/locks/session?app=billing&process=report-{round}-{i}&eta=60s&dedupe_key=report:{round}&window=24h"
+                    );
+                    tokio::spawn(async move { tokio_tungstenite::client_async(url.as_str(), stream).await })
+                })
+                .collect();
+
+            let mut won = Vec::new();
+            for racer in racers {
+                match racer.await.unwrap() {
+                    Ok((ws, _)) => won.push(ws),
+                    Err(tungstenite::Error::Http(res)) => assert_eq!(res.status(), axum::http::StatusCode::LOCKED),
+                    Err(e) => panic!("unexpected error {e:?}"),
+                }
+            }
+            assert_eq!(won.len(), 1);
+
+            let first = loop {
+                if let tungstenite::Message::Text(text) = won[0].next().await.unwrap().unwrap() {
+                    break serde_json::from_str::<serde_json::Value>(&text).unwrap();
+                }
+            };
+            assert_eq!(events.try_recv().unwrap().process_id.as_ref(), first["lock"]["process_id"].as_str().unwrap());
+            assert!(events.try_recv().is_err());
+
+            sessions.append(&mut won);
+        }
+    }
+}
@@ -0,0 +1,472 @@
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{middleware, Json, Router};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{coordination, db, elections};
+use crate::db::cooldowns::CooldownPolicy;
+use crate::db::repository::{get_process_by_id, get_processes, renew_process, Cursor};
+use crate::db::Database;
+use crate::models::{DeadLetter, OperationStatus, ResponseCountdown, ResponseLeader, ResponseProcess, ResponseWebhook};
+use crate::scheduler::{self, PlannedChange, TaskRun, TaskStatus};
+
+use super::error::ApiError;
+use super::middleware::mw_require_store_v1;
+use super::params::{Eta, GetProcesses, Heartbeat, NewProcess, ProcessData, UpdateProcess};
+use super::routes::{acquire_lock, change_lock_status, LockState};
+
+/// Every v1 success body is `{"data": ..., "meta": ...}`, every error body is
+/// `{"error": {"code": ..., "message": ...}}`.
+#[derive(Debug, Serialize, ToSchema)]
+#[aliases(
+    LockEnvelope = Envelope<ResponseProcess>,
+    LockListEnvelope = Envelope<Vec<ResponseProcess>>,
+    WebhookEnvelope = Envelope<ResponseWebhook>,
+    WebhookListEnvelope = Envelope<Vec<ResponseWebhook>>,
+    DeadLetterListEnvelope = Envelope<Vec<DeadLetter>>,
+    SchedulerEnvelope = Envelope<Vec<TaskStatus>>,
+    TaskRunEnvelope = Envelope<TaskRun>,
+    PlanEnvelope = Envelope<Vec<PlannedChange>>,
+    LeaderEnvelope = Envelope<ResponseLeader>,
+    CountdownEnvelope = Envelope<ResponseCountdown>,
+)]
+pub(super) struct Envelope<T> {
+    data: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Meta>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct Meta {
+    /// Pass as `cursor` to fetch the next page, `null` on the last page.
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct ErrorEnvelope {
+    error: ErrorDetail,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct ErrorDetail {
+    #[schema(example = "ProcessExist")]
+    code: String,
+    message: String,
+    /// With `AlreadyDone`: the run that completed the work.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    done_by: Option<DoneRun>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct DoneRun {
+    #[schema(format = Uuid)]
+    process_id: String,
+    completed_at: DateTime<Utc>,
+}
+
+impl<T> Envelope<T> {
+    pub(super) fn new(data: T) -> Self {
+        Envelope { data, meta: None }
+    }
+}
+
+pub(super) struct V1Error(ApiError);
+
+pub(super) type Result<T> = core::result::Result<T, V1Error>;
+
+impl IntoResponse for V1Error {
+    fn into_response(self) -> Response {
+        error!("api error: {:?}", &self.0);
+
+        let code = self.0.to_string();
+        let done_by = match &self.0 {
+            ApiError::AlreadyDone { process_id, completed_at } => Some(DoneRun {
+                process_id: process_id.clone(),
+                completed_at: *completed_at,
+            }),
+            _ => None,
+        };
+        let retry_after = self.0.retry_after();
+        let (status, message) = self.0.status_and_message();
+
+        (status, retry_after, Json(ErrorEnvelope { error: ErrorDetail { code, message, done_by } })).into_response()
+    }
+}
+
+impl From<ApiError> for V1Error {
+    fn from(err: ApiError) -> Self {
+        V1Error(err)
+    }
+}
+
+impl From<db::error::Error> for V1Error {
+    fn from(err: db::error::Error) -> Self {
+        V1Error(err.into())
+    }
+}
+
+impl From<coordination::error::Error> for V1Error {
+    fn from(err: coordination::error::Error) -> Self {
+        V1Error(err.into())
+    }
+}
+
+impl From<elections::error::Error> for V1Error {
+    fn from(err: elections::error::Error) -> Self {
+        V1Error(err.into())
+    }
+}
+
+impl From<scheduler::error::Error> for V1Error {
+    fn from(err: scheduler::error::Error) -> Self {
+        V1Error(err.into())
+    }
+}
+
+impl From<JsonRejection> for V1Error {
+    fn from(rejection: JsonRejection) -> Self {
+        V1Error(rejection.into())
+    }
+}
+
+impl From<QueryRejection> for V1Error {
+    fn from(rejection: QueryRejection) -> Self {
+        V1Error(rejection.into())
+    }
+}
+
+impl From<PathRejection> for V1Error {
+    fn from(rejection: PathRejection) -> Self {
+        V1Error(rejection.into())
+    }
+}
+
+pub fn routes(db: Database, cooldowns: CooldownPolicy) -> Router {
+    Router::new()
+        .route("/v1/locks", get(list_locks).post(create_lock))
+        .route(
+            "/v1/locks/:lock_id",
+            get(get_lock).patch(update_lock).delete(release_lock),
+        )
+        .route("/v1/locks/:lock_id/heartbeat", post(heartbeat_lock))
+        .route_layer(middleware::from_fn_with_state(db.clone(), mw_require_store_v1))
+        .with_state(LockState { db, cooldowns })
+}
+
+// Extractor rejections are taken as `Result` so they are reported in the v1 envelope too.
+
+#[utoipa::path(
+    post,
+    path = "/v1/locks",
+    tag = "locks",
+    request_body = NewProcess,
+    responses(
+        (status = 201, description = "Lock acquired", body = LockEnvelope),
+        (status = 400, description = "Invalid request", body = ErrorEnvelope),
+        (status = 409, description = "A run with this dedupe key is already done", body = ErrorEnvelope),
+        (status = 423, description = "A lock for this process is already held", body = ErrorEnvelope),
+        (status = 429, description = "The process is cooling down, see `Retry-After`", body = ErrorEnvelope,
+            headers(("Retry-After" = u64, description = "Seconds until the cooldown is over"))),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn create_lock(
+    State(db): State<Database>,
+    State(cooldowns): State<CooldownPolicy>,
+    payload: core::result::Result<Json<NewProcess>, JsonRejection>,
+) -> Result<Response> {
+    let Json(payload) = payload?;
+
+    let id = acquire_lock(&db, &cooldowns, payload).await?;
+    let lock = get_process_by_id(&db, &id).await?;
+
+    let location = [(header::LOCATION, format!("/v1/locks/{id}"))];
+
+    Ok((StatusCode::CREATED, location, Json(Envelope::new(lock.to_response()))).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/locks/{lock_id}",
+    tag = "locks",
+    params(("lock_id" = Uuid, Path, description = "Lock id")),
+    responses(
+        (status = 200, description = "Lock found", body = LockEnvelope),
+        (status = 404, description = "Lock not found", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn get_lock(
+    State(db): State<Database>,
+    lock_id: core::result::Result<Path<Uuid>, PathRejection>,
+) -> Result<Json<Envelope<ResponseProcess>>> {
+    let Path(lock_id) = lock_id?;
+
+    let lock = get_process_by_id(&db, &lock_id.to_string()).await?;
+
+    Ok(Json(Envelope::new(lock.to_response())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/locks",
+    tag = "locks",
+    params(GetProcesses),
+    responses(
+        (status = 200, description = "One page of locks", body = LockListEnvelope),
+        (status = 400, description = "Invalid filter", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn list_locks(
+    State(db): State<Database>,
+    params: core::result::Result<Query<GetProcesses>, QueryRejection>,
+) -> Result<Json<Envelope<Vec<ResponseProcess>>>> {
+    let Query(params) = params?;
+
+    let page = get_processes(&db, params.into_filter()?).await?;
+
+    Ok(Json(Envelope {
+        data: page.processes.iter().map(|p| p.to_response()).collect(),
+        meta: Some(Meta {
+            next_cursor: page.next_cursor.as_ref().map(Cursor::encode),
+        }),
+    }))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/v1/locks/{lock_id}",
+    tag = "locks",
+    params(("lock_id" = Uuid, Path, description = "Lock id")),
+    request_body = UpdateProcess,
+    responses(
+        (status = 200, description = "Status changed", body = LockEnvelope),
+        (status = 400, description = "Status can't be changed", body = ErrorEnvelope),
+        (status = 404, description = "Lock not found", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn update_lock(
+    State(db): State<Database>,
+    lock_id: core::result::Result<Path<Uuid>, PathRejection>,
+    payload: core::result::Result<Json<UpdateProcess>, JsonRejection>,
+) -> Result<Json<Envelope<ResponseProcess>>> {
+    let Path(lock_id) = lock_id?;
+    let Json(payload) = payload?;
+
+    set_status(&db, lock_id, payload.get_status()).await
+}
+
+/// Releasing a lock marks it `Completed`, the cleaner deletes it later.
+#[utoipa::path(
+    delete,
+    path = "/v1/locks/{lock_id}",
+    tag = "locks",
+    params(("lock_id" = Uuid, Path, description = "Lock id")),
+    responses(
+        (status = 200, description = "Lock released", body = LockEnvelope),
+        (status = 400, description = "Lock can't be released", body = ErrorEnvelope),
+        (status = 404, description = "Lock not found", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn release_lock(
+    State(db): State<Database>,
+    lock_id: core::result::Result<Path<Uuid>, PathRejection>,
+) -> Result<Json<Envelope<ResponseProcess>>> {
+    let Path(lock_id) = lock_id?;
+
+    set_status(&db, lock_id, OperationStatus::Completed).await
+}
+
+/// Moves the lock deadline to `eta` from now, for holders that outlive their first estimate.
+#[utoipa::path(
+    post,
+    path = "/v1/locks/{lock_id}/heartbeat",
+    tag = "locks",
+    params(("lock_id" = Uuid, Path, description = "Lock id")),
+    request_body = Heartbeat,
+    responses(
+        (status = 200, description = "Lock renewed", body = LockEnvelope),
+        (status = 400, description = "Lock is already finished", body = ErrorEnvelope),
+        (status = 404, description = "Lock not found", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn heartbeat_lock(
+    State(db): State<Database>,
+    lock_id: core::result::Result<Path<Uuid>, PathRejection>,
+    payload: core::result::Result<Json<Heartbeat>, JsonRejection>,
+) -> Result<Json<Envelope<ResponseProcess>>> {
+    let Path(lock_id) = lock_id?;
+    let Json(payload) = payload?;
+    let id = lock_id.to_string();
+
+    renew_process(&db, &id, payload.eta_to_u64()?).await?;
+    let lock = get_process_by_id(&db, &id).await?;
+
+    Ok(Json(Envelope::new(lock.to_response())))
+}
+
+async fn set_status(
+    db: &Database,
+    lock_id: Uuid,
+    status: OperationStatus,
+) -> Result<Json<Envelope<ResponseProcess>>> {
+    let id = lock_id.to_string();
+
+    change_lock_status(db, &id, status).await?;
+    let lock = get_process_by_id(db, &id).await?;
+
+    Ok(Json(Envelope::new(lock.to_response())))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::db::ConnectionState;
+
+    async fn send(app: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        let req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+            .unwrap();
+
+        let res = app.clone().oneshot(req).await.unwrap();
+        let status = res.status();
+        let bytes = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_lock_lifecycle() {
+        let app = routes(Database::in_memory(), CooldownPolicy::default());
+        let new_lock = json!({ "app": "billing", "process": "invoices", "eta": "60s" });
+
+        let (status, body) = send(&app, "POST", "/v1/locks", Some(new_lock.clone())).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let id = body["data"]["process_id"].as_str().unwrap().to_string();
+
+        let (status, body) = send(&app, "POST", "/v1/locks", Some(new_lock)).await;
+        assert_eq!(status, StatusCode::LOCKED);
+        assert_eq!(body["error"]["code"], "ProcessExist");
+
+        let (status, body) = send(&app, "GET", &format!("/v1/locks/{id}"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "New");
+
+        let (status, body) = send(&app, "POST", &format!("/v1/locks/{id}/heartbeat"), Some(json!({ "eta": "2m" }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["data"]["sla"].as_u64().unwrap() >= 120);
+
+        let (status, body) = send(&app, "DELETE", &format!("/v1/locks/{id}"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "Completed");
+
+        let (status, _) = send(&app, "POST", &format!("/v1/locks/{id}/heartbeat"), Some(json!({ "eta": "2m" }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = send(&app, "GET", "/v1/locks?app=billing&status=Completed", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+        assert!(body["meta"]["next_cursor"].is_null());
+
+        let (status, _) = send(&app, "GET", &format!("/v1/locks/{}", Uuid::now_v7()), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_dedupe_key_runs_once_per_window() {
+        let app = routes(Database::in_memory(), CooldownPolicy::default());
+        let run = |process: &str| {
+            json!({ "app": "billing", "process": process, "eta": "60s", "dedupe_key": "report:2026-10-18", "window": "24h" })
+        };
+
+        let (status, body) = send(&app, "POST", "/v1/locks", Some(run("report"))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let id = body["data"]["process_id"].as_str().unwrap().to_string();
+
+        // Failed runs don't count.
+        send(&app, "PATCH", &format!("/v1/locks/{id}"), Some(json!({ "status": "Canceled" }))).await;
+        let (status, body) = send(&app, "POST", "/v1/locks", Some(run("report"))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let id = body["data"]["process_id"].as_str().unwrap().to_string();
+
+        send(&app, "DELETE", &format!("/v1/locks/{id}"), None).await;
+        for process in ["report", "report-retry"] {
+            let (status, body) = send(&app, "POST", "/v1/locks", Some(run(process))).await;
+            assert_eq!(status, StatusCode::CONFLICT);
+            assert_eq!(body["error"]["code"], "AlreadyDone");
+            assert_eq!(body["error"]["done_by"]["process_id"], id.as_str());
+        }
+
+        let (status, _) = send(&app, "POST", "/v1/locks", Some(json!({ "app": "billing", "process": "report", "eta": "60s", "window": "24h" }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_rejects_with_retry_after() {
+        let policy = CooldownPolicy::new([("crm/sync".to_string(), 3600), ("crm/import".to_string(), 3600)].into());
+        let app = routes(Database::in_memory(), policy);
+        let sync = json!({ "app": "crm", "process": "sync", "eta": "60s" });
+
+        let (status, body) = send(&app, "POST", "/v1/locks", Some(sync.clone())).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let id = body["data"]["process_id"].as_str().unwrap().to_string();
+
+        // Held, not cooling down yet.
+        let (status, _) = send(&app, "POST", "/v1/locks", Some(sync.clone())).await;
+        assert_eq!(status, StatusCode::LOCKED);
+
+        send(&app, "PATCH", &format!("/v1/locks/{id}"), Some(json!({ "status": "Canceled" }))).await;
+        let req = Request::post("/v1/locks")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(sync.to_string()))
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = res.headers()[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+        assert!((3590..=3600).contains(&retry_after));
+
+        // The request's `0s` wins over the policy's hour: taken again right away.
+        let (status, body) = send(&app, "POST", "/v1/locks", Some(json!({ "app": "crm", "process": "import", "eta": "60s", "cooldown": "0s" }))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let id = body["data"]["process_id"].as_str().unwrap().to_string();
+        send(&app, "DELETE", &format!("/v1/locks/{id}"), None).await;
+        let (status, body) = send(&app, "POST", "/v1/locks", Some(json!({ "app": "crm", "process": "import", "eta": "60s" }))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let id = body["data"]["process_id"].as_str().unwrap().to_string();
+        send(&app, "DELETE", &format!("/v1/locks/{id}"), None).await;
+        let (status, body) = send(&app, "POST", "/v1/locks", Some(json!({ "app": "crm", "process": "import", "eta": "60s" }))).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body["error"]["code"], "CoolingDown");
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_store_answers_in_the_v1_envelope() {
+        let db = Database::in_memory();
+        db.set_state(ConnectionState::Reconnecting);
+        let app = routes(db, CooldownPolicy::default());
+
+        let (status, body) = send(&app, "GET", &format!("/v1/locks/{}", Uuid::now_v7()), None).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"]["code"], "ServiceUnavailable");
+        assert_eq!(body["error"]["message"], "Storage is unavailable");
+    }
+}
@@ -0,0 +1,141 @@
+use axum::extract::rejection::{JsonRejection, PathRejection};
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get};
+use axum::{middleware, Json, Router};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::db::webhooks::{create_webhook, delete_webhook, get_dead_letters, get_webhooks};
+use crate::db::Database;
+use crate::models::{DeadLetter, ResponseWebhook, WebhookEvent};
+
+use super::error::ApiError;
+use super::middleware::mw_require_store_v1;
+use super::v1::{Envelope, Result};
+
+pub fn routes(db: Database) -> Router {
+    Router::new()
+        .route("/v1/webhooks", get(list_webhooks).post(subscribe))
+        .route("/v1/webhooks/dead_letters", get(list_dead_letters))
+        .route("/v1/webhooks/:webhook_id", delete(unsubscribe))
+        .route_layer(middleware::from_fn_with_state(db.clone(), mw_require_store_v1))
+        .with_state(db)
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(super) struct NewWebhook {
+    #[schema(example = "https://billing.internal/hooks/flowlocker")]
+    url: String,
+    /// Key of the `X-Flowlocker-Signature` HMAC, never returned.
+    secret: String,
+    /// Only locks of this app, every app when omitted.
+    app: Option<String>,
+    /// Only locks of this process name, every process when omitted.
+    process: Option<String>,
+    /// Every event when omitted or empty.
+    #[serde(default)]
+    events: Vec<WebhookEvent>,
+}
+
+impl NewWebhook {
+    fn validate(&self) -> core::result::Result<(), ApiError> {
+        let url = reqwest::Url::parse(&self.url).map_err(|_| ApiError::BadRequest("Invalid webhook url".to_string()))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(ApiError::BadRequest("Webhook url must be http or https".to_string()));
+        }
+
+        if self.secret.is_empty() {
+            return Err(ApiError::BadRequest("Webhook secret is required".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/webhooks",
+    tag = "webhooks",
+    request_body = NewWebhook,
+    responses(
+        (status = 201, description = "Subscription created", body = WebhookEnvelope),
+        (status = 400, description = "Invalid subscription", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn subscribe(
+    State(db): State<Database>,
+    payload: core::result::Result<Json<NewWebhook>, JsonRejection>,
+) -> Result<Response> {
+    let Json(payload) = payload?;
+    payload.validate()?;
+
+    let events = if payload.events.is_empty() {
+        WebhookEvent::ALL.to_vec()
+    } else {
+        payload.events
+    };
+
+    let webhook = create_webhook(&db, payload.url, payload.secret, payload.app, payload.process, events).await?;
+
+    let location = [(header::LOCATION, format!("/v1/webhooks/{}", webhook.webhook_id))];
+
+    Ok((StatusCode::CREATED, location, Json(Envelope::new(webhook.to_response()))).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/webhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Every subscription", body = WebhookListEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn list_webhooks(State(db): State<Database>) -> Result<Json<Envelope<Vec<ResponseWebhook>>>> {
+    let webhooks = get_webhooks(&db).await?;
+
+    Ok(Json(Envelope::new(webhooks.iter().map(|w| w.to_response()).collect())))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/webhooks/{webhook_id}",
+    tag = "webhooks",
+    params(("webhook_id" = Uuid, Path, description = "Webhook id")),
+    responses(
+        (status = 204, description = "Subscription deleted"),
+        (status = 404, description = "Webhook not found", body = ErrorEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn unsubscribe(
+    State(db): State<Database>,
+    webhook_id: core::result::Result<Path<Uuid>, PathRejection>,
+) -> Result<StatusCode> {
+    let Path(webhook_id) = webhook_id?;
+
+    delete_webhook(&db, &webhook_id.to_string()).await.map_err(|e| match e {
+        crate::db::error::Error::RecordNotFound => ApiError::NotFound("Webhook not found".to_string()),
+        e => e.into(),
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Deliveries that still failed after the last retry.
+#[utoipa::path(
+    get,
+    path = "/v1/webhooks/dead_letters",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Undelivered events", body = DeadLetterListEnvelope),
+        (status = 503, description = "Storage is unavailable", body = ErrorEnvelope),
+    )
+)]
+pub(super) async fn list_dead_letters(State(db): State<Database>) -> Result<Json<Envelope<Vec<DeadLetter>>>> {
+    Ok(Json(Envelope::new(get_dead_letters(&db).await?)))
+}
@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, instrument};
+
+use crate::db::repository::get_finished_processes;
+use crate::db::Database;
+use crate::scheduler::error::Result;
+use crate::scheduler::task::{Schedule, Task, TaskReport};
+use crate::time;
+
+/// Processes read from the store at once.
+const PAGE_SIZE: u32 = 1000;
+
+/// The `archive_export` task: appends processes that finished since its
+/// previous run to `<dir>/locks-<YYYY-MM-DD>.jsonl`, one lock per line.
+///
+/// Runs must be closer together than the retention period, or processes can
+/// be purged before they are exported. After a restart everything still
+/// stored is exported again, so a lock may appear more than once.
+#[derive(Debug)]
+pub struct ArchiveExport {
+    db: Database,
+    dir: PathBuf,
+    schedule: String,
+    // Processes finished before this second have been exported.
+    exported_until: Mutex<u64>,
+}
+
+impl ArchiveExport {
+    pub fn new(db: Database, dir: impl Into<PathBuf>, schedule: impl Into<String>) -> Self {
+        ArchiveExport {
+            db,
+            dir: dir.into(),
+            schedule: schedule.into(),
+            exported_until: Mutex::new(0),
+        }
+    }
+
+    #[instrument(skip(self))]
+    async fn export(&self) -> Result<TaskReport> {
+        // Processes updated in the current second may still change, they go with the next run.
+        let until = time::from_epoch()?;
+        let from = *self.exported_until.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut page = get_finished_processes(&self.db, from, until, None, PAGE_SIZE).await?;
+        if !page.is_empty() {
+            let day = DateTime::from_timestamp(until as i64, 0).unwrap_or_default().format("%Y-%m-%d");
+            let path = self.dir.join(format!("locks-{day}.jsonl"));
+
+            tokio::fs::create_dir_all(&self.dir).await?;
+            let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(&path).await?;
+            let mut bytes = 0;
+
+            while let Some(last) = page.last() {
+                let after = Some((last.updated_at, last.process_id.to_string()));
+
+                let mut lines = Vec::new();
+                for p in &page {
+                    lines.extend(serde_json::to_vec(&p.to_response())?);
+                    lines.push(b'\n');
+                }
+                file.write_all(&lines).await?;
+                bytes += lines.len();
+
+                if page.len() < PAGE_SIZE as usize {
+                    break;
+                }
+                page = get_finished_processes(&self.db, from, until, after, PAGE_SIZE).await?;
+            }
+            file.flush().await?;
+
+            info!(name = "locks archived", path = %path.display(), bytes);
+        }
+
+        *self.exported_until.lock().unwrap_or_else(|e| e.into_inner()) = until;
+
+        Ok(TaskReport::default())
+    }
+}
+
+#[async_trait]
+impl Task for ArchiveExport {
+    fn name(&self) -> &'static str {
+        "archive_export"
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Cron(self.schedule.clone())
+    }
+
+    async fn run(&self) -> Result<TaskReport> {
+        self.export().await
+    }
+}
@@ -1,54 +1,212 @@
-use crate::db::repository::{delete_process_by_id, get_running_processes, update_process_status};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::db::repository::{get_expired_processes, get_unfinished_processes, outdate_expired_processes};
 use crate::db::Database;
-use crate::models::OperationStatus;
+use crate::models::{OperationStatus, Process, WebhookEvent};
 use crate::scheduler::error::Result;
+use crate::scheduler::task::{Action, PlannedChange, Schedule, Task, TaskReport};
 use crate::time;
-use tracing::{debug, info, instrument};
+use crate::webhooks::Dispatcher;
+use metrics::counter;
+use tracing::{debug, info, instrument, warn};
 
-#[derive(Debug, Clone)]
+/// The `expiry` task: marks processes past their SLA `Outdated` and warns
+/// about the ones getting close.
+#[derive(Debug)]
 pub struct Cleaner {
     db: Database,
+    interval: Duration,
+    sla_warnings: SlaWarnings,
+    // Highest threshold already announced per process, with the SLA it was
+    // computed for: a renewed lock is warned about again.
+    warned: Mutex<HashMap<String, (u64, u64)>>,
 }
 
-const DEFAULT_DELETION_INTERVAL: u64 = 600;
-
-// Create TASK abstraction
-// Change behavior of Cleaner
+/// Early warnings before a process is marked `Outdated`.
+#[derive(Debug, Clone, Default)]
+pub struct SlaWarnings {
+    /// Ascending percents of the SLA, no warnings when empty.
+    pub thresholds: Vec<u64>,
+    pub webhooks: Option<Dispatcher>,
+}
 
 impl Cleaner {
-    pub fn new(db: Database) -> Self {
-        Cleaner { db }
+    pub fn new(db: Database, interval: Duration, sla_warnings: SlaWarnings) -> Self {
+        Cleaner {
+            db,
+            interval,
+            sla_warnings,
+            warned: Mutex::new(HashMap::new()),
+        }
     }
     #[instrument(skip(self))]
-    pub async fn run(&self) -> Result<()> {
+    async fn expire(&self) -> Result<TaskReport> {
         let now_time = time::from_epoch()?;
 
         debug!(name = "job_events", status = "started");
-        let processes = get_running_processes(&self.db).await?;
-
-        if processes.is_some() {
-            for p in processes.unwrap() {
-                if p.status.is_canceled() || p.status.is_completed() || p.status.is_outdated() {
-                    if now_time > p.updated_at + DEFAULT_DELETION_INTERVAL {
-                        delete_process_by_id(&self.db, &p.process_id).await?;
-                        info!(name = "process deleted", process_id = %p.process_id);
-                    }
-                    continue;
-                }
+        let outdated = outdate_expired_processes(&self.db, now_time).await?;
+        for p in &outdated {
+            info!(
+                name = "process status changed",
+                process_id = %p.process_id,
+                status = OperationStatus::Outdated.to_string()
+            );
+        }
 
-                if now_time > p.create_at + p.sla {
-                    update_process_status(&self.db, &p.process_id, OperationStatus::Outdated)
-                        .await?;
-                    info!(
-                        name = "process status changed",
-                        status = OperationStatus::Outdated.to_string()
-                    );
+        let mut running = HashSet::new();
+        if !self.sla_warnings.thresholds.is_empty() {
+            for p in get_unfinished_processes(&self.db).await? {
+                running.insert(p.process_id.to_string());
+                if let Some(consumed) = self.crossed_threshold(&p, now_time) {
+                    self.warn_sla(&p, consumed).await;
                 }
             }
         }
 
+        self.warned
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|id, _| running.contains(id));
+
         debug!(name = "job_events", status = "completed successfully");
 
-        Ok(())
+        Ok(TaskReport {
+            outdated: outdated.len() as u64,
+            ..TaskReport::default()
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        let now_time = time::from_epoch()?;
+
+        Ok(get_expired_processes(&self.db, now_time)
+            .await?
+            .into_iter()
+            .map(|p| PlannedChange {
+                task: self.name().to_string(),
+                action: Action::Outdate,
+                process: p.to_response(),
+            })
+            .collect())
+    }
+
+    /// Percent of the SLA consumed by `p`, if it crossed a threshold that
+    /// hasn't been announced yet.
+    fn crossed_threshold(&self, p: &Process, now_time: u64) -> Option<u64> {
+        if p.sla == 0 {
+            return None;
+        }
+
+        let consumed = now_time.saturating_sub(p.create_at) * 100 / p.sla;
+        let threshold = *self.sla_warnings.thresholds.iter().rev().find(|t| consumed >= **t)?;
+
+        let mut warned = self.warned.lock().unwrap_or_else(|e| e.into_inner());
+        match warned.get(p.process_id.as_ref()) {
+            Some(&(sla, announced)) if sla == p.sla && announced >= threshold => None,
+            _ => {
+                warned.insert(p.process_id.to_string(), (p.sla, threshold));
+                Some(consumed)
+            }
+        }
+    }
+
+    async fn warn_sla(&self, p: &Process, consumed: u64) {
+        warn!(
+            name = "sla_warning",
+            process_id = %p.process_id,
+            app = %p.app,
+            process_name = %p.process_name,
+            sla_consumed = consumed
+        );
+        counter!("flowlocker_sla_warnings_total", 1, "app" => p.app.to_string());
+
+        if let Some(webhooks) = &self.sla_warnings.webhooks {
+            webhooks.notify(WebhookEvent::SlaWarning, p, Some(consumed)).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Task for Cleaner {
+    fn name(&self) -> &'static str {
+        "expiry"
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(self.interval)
+    }
+
+    async fn run(&self) -> Result<TaskReport> {
+        self.expire().await
+    }
+
+    async fn plan(&self) -> Result<Option<Vec<PlannedChange>>> {
+        Ok(Some(Cleaner::plan(self).await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::repository::{acquire_process, get_process_by_id};
+    use crate::scheduler::Registry;
+
+    #[tokio::test]
+    async fn test_dry_run_changes_nothing() {
+        let db = Database::in_memory();
+        let id = acquire_process(&db, "billing".to_string(), "invoices".to_string(), 0).await.unwrap();
+        let registry = Registry::new(Duration::from_secs(30))
+            .dry_run(true)
+            .register(Cleaner::new(db.clone(), Duration::from_secs(30), SlaWarnings::default()));
+
+        // Past its SLA once the second is over.
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        let run = registry.run_now("expiry").await.unwrap();
+        assert_eq!((run.outdated, run.dry_run), (1, true));
+
+        let plan = registry.plan().await.unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_eq!((plan[0].action, plan[0].process.process_id.as_ref()), (Action::Outdate, id.as_str()));
+
+        assert_eq!(get_process_by_id(&db, &id).await.unwrap().status, OperationStatus::New);
+    }
+
+    #[test]
+    fn test_each_threshold_is_announced_once() {
+        let cleaner = Cleaner::new(
+            Database::in_memory(),
+            Duration::from_secs(30),
+            SlaWarnings {
+                thresholds: vec![80, 95],
+                webhooks: None,
+            },
+        );
+        let mut p = Process {
+            process_id: "id".into(),
+            app: "billing".into(),
+            process_name: "invoices".into(),
+            status: OperationStatus::New,
+            create_at: 1000,
+            updated_at: 1000,
+            ended_at: 0,
+            sla: 100,
+        };
+
+        assert_eq!(cleaner.crossed_threshold(&p, 1050), None);
+        assert_eq!(cleaner.crossed_threshold(&p, 1085), Some(85));
+        assert_eq!(cleaner.crossed_threshold(&p, 1090), None);
+        assert_eq!(cleaner.crossed_threshold(&p, 1096), Some(96));
+        assert_eq!(cleaner.crossed_threshold(&p, 1099), None);
+
+        // Renewed: the deadline moved, so the thresholds apply again.
+        p.sla = 200;
+        assert_eq!(cleaner.crossed_threshold(&p, 1099), None);
+        assert_eq!(cleaner.crossed_threshold(&p, 1170), Some(85));
     }
 }
@@ -0,0 +1,263 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, instrument, warn};
+
+use crate::db::error::Result;
+use crate::db::repository::{get_process_by_id, get_unfinished_processes, outdate_process_if_expired};
+use crate::db::Database;
+use crate::scheduler::Leadership;
+use crate::models::{OperationStatus, Process};
+use crate::time;
+
+/// Pause before the index is rebuilt again when the store can't be read.
+const REBUILD_RETRY: Duration = Duration::from_secs(5);
+
+/// Marks processes `Outdated` as soon as their deadline, `create_at + sla`,
+/// is over rather than on the next `expiry` run, which stays as a safety net.
+///
+/// The index follows the processes published by the store and is rebuilt
+/// from it at startup and whenever events were missed.
+#[derive(Debug)]
+pub struct Deadlines {
+    db: Database,
+    leadership: Leadership,
+}
+
+#[derive(Debug, Default)]
+struct Index {
+    heap: BinaryHeap<Reverse<(u64, String)>>,
+    // Current deadline per process, heap entries that don't match are stale.
+    deadlines: HashMap<String, u64>,
+}
+
+impl Deadlines {
+    pub fn new(db: Database) -> Self {
+        Deadlines {
+            db,
+            leadership: Leadership::always(),
+        }
+    }
+
+    /// Processes are only outdated while `leadership` holds, like scheduled runs.
+    pub fn leadership(mut self, leadership: Leadership) -> Self {
+        self.leadership = leadership;
+        self
+    }
+
+    #[instrument(skip_all)]
+    pub async fn run(self, shutdown: CancellationToken) {
+        // Subscribed first, so nothing created during the rebuild is missed.
+        let mut events = self.db.subscribe();
+        let mut index = Index::default();
+        let mut rebuild_at = Some(Instant::now());
+        let mut leadership = self.leadership.clone();
+
+        loop {
+            let due = if leadership.is_leader() { index.next().map(instant_after) } else { None };
+            let wake = match (rebuild_at, due) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                // Locks taken on other instances while following were never
+                // published here, the index is rebuilt before leading.
+                _ = leadership.changed() => if leadership.is_leader() {
+                    rebuild_at = Some(Instant::now());
+                },
+                event = events.recv() => match event {
+                    Ok(p) => index.track(&p),
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!(caller = "deadlines", event = "events missed, rebuilding", missed);
+                        rebuild_at = Some(Instant::now());
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = sleep_until(wake), if wake.is_some() => {
+                    if rebuild_at.is_some_and(|at| at <= Instant::now()) {
+                        rebuild_at = match self.rebuild(&mut index).await {
+                            Ok(()) => None,
+                            Err(e) => {
+                                warn!(caller = "deadlines", event = "rebuild failed", error = ?e);
+                                Some(Instant::now() + REBUILD_RETRY)
+                            }
+                        };
+                    }
+                    if leadership.is_leader() {
+                        self.expire_due(&mut index).await;
+                    }
+                }
+            }
+        }
+
+        info!(caller = "deadlines", event = "deadlines stopped");
+    }
+
+    async fn rebuild(&self, index: &mut Index) -> Result<()> {
+        let processes = get_unfinished_processes(&self.db).await?;
+
+        *index = Index::default();
+        for p in &processes {
+            index.track(p);
+        }
+
+        debug!(caller = "deadlines", event = "index rebuilt", processes = processes.len());
+        Ok(())
+    }
+
+    async fn expire_due(&self, index: &mut Index) {
+        let Ok(now) = time::from_epoch() else {
+            return;
+        };
+
+        while let Some(id) = index.pop_due(now) {
+            match outdate_process_if_expired(&self.db, &id, now).await {
+                Ok(Some(_)) => info!(
+                    name = "process status changed",
+                    process_id = %id,
+                    status = OperationStatus::Outdated.to_string()
+                ),
+                // Renewed since it was indexed: follow its new deadline.
+                Ok(None) => match get_process_by_id(&self.db, &id).await {
+                    Ok(p) if p.create_at + p.sla >= now => index.track(&p),
+                    _ => {}
+                },
+                // Left to the `expiry` task.
+                Err(e) => warn!(caller = "deadlines", event = "can't outdate process", process_id = %id, error = ?e),
+            }
+        }
+    }
+}
+
+impl Index {
+    fn track(&mut self, p: &Process) {
+        let id = p.process_id.to_string();
+
+        if p.status.is_finished() {
+            self.deadlines.remove(&id);
+            return;
+        }
+
+        let deadline = p.create_at + p.sla;
+        if self.deadlines.insert(id.clone(), deadline) != Some(deadline) {
+            self.heap.push(Reverse((deadline, id)));
+        }
+    }
+
+    /// Earliest deadline, stale entries are dropped on the way.
+    fn next(&mut self) -> Option<u64> {
+        while let Some(Reverse((deadline, id))) = self.heap.peek() {
+            if self.deadlines.get(id) == Some(deadline) {
+                return Some(*deadline);
+            }
+            self.heap.pop();
+        }
+
+        None
+    }
+
+    /// A process whose deadline is over at `now`, matching the `expiry` task:
+    /// a process expires once the second of its deadline has passed.
+    fn pop_due(&mut self, now: u64) -> Option<String> {
+        if self.next()? >= now {
+            return None;
+        }
+
+        let Reverse((_, id)) = self.heap.pop()?;
+        self.deadlines.remove(&id);
+        Some(id)
+    }
+}
+
+/// When the deadline, in seconds since the epoch, is over.
+fn instant_after(deadline: u64) -> Instant {
+    let at = UNIX_EPOCH + Duration::from_secs(deadline + 1);
+    Instant::now() + at.duration_since(SystemTime::now()).unwrap_or_default()
+}
+
+async fn sleep_until(wake: Option<Instant>) {
+    if let Some(at) = wake {
+        tokio::time::sleep_until(at).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::repository::{acquire_process, renew_process};
+    use crate::db::Backend;
+    use crate::scheduler::Election;
+
+    #[tokio::test]
+    async fn test_outdates_at_the_deadline() {
+        let db = Database::in_memory();
+        let before_start = acquire_process(&db, "app".to_string(), "indexed".to_string(), 1).await.unwrap();
+
+        let shutdown = CancellationToken::new();
+        let task = tokio::spawn(Deadlines::new(db.clone()).run(shutdown.clone()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let tracked = acquire_process(&db, "app".to_string(), "tracked".to_string(), 1).await.unwrap();
+        let renewed = acquire_process(&db, "app".to_string(), "renewed".to_string(), 1).await.unwrap();
+        renew_process(&db, &renewed, 10).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+
+        let status = |id: String| {
+            let db = db.clone();
+            async move { get_process_by_id(&db, &id).await.unwrap().status }
+        };
+        assert_eq!(status(before_start).await, OperationStatus::Outdated);
+        assert_eq!(status(tracked).await, OperationStatus::Outdated);
+        assert_eq!(status(renewed).await, OperationStatus::New);
+
+        shutdown.cancel();
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_only_the_leader_outdates() {
+        let db = Database::in_memory();
+        let id = acquire_process(&db, "app".to_string(), "follower".to_string(), 1).await.unwrap();
+
+        // Never campaigns, so never leads.
+        let follower = Election::new(db.clone(), Duration::from_secs(1)).leadership();
+        let shutdown = CancellationToken::new();
+        let task = tokio::spawn(Deadlines::new(db.clone()).leadership(follower).run(shutdown.clone()));
+
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        assert_eq!(get_process_by_id(&db, &id).await.unwrap().status, OperationStatus::New);
+
+        shutdown.cancel();
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rebuilds_on_becoming_leader() {
+        let db = Database::in_memory();
+        let election = Election::new(db.clone(), Duration::from_secs(1));
+        let shutdown = CancellationToken::new();
+        let task = tokio::spawn(Deadlines::new(db.clone()).leadership(election.leadership()).run(shutdown.clone()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Written straight to the store, as by another instance: no event here.
+        let Backend::Sqlite(store) = db.backend().unwrap() else {
+            unreachable!()
+        };
+        let id = store.acquire_process("app".to_string(), "elsewhere".to_string(), 1).await.unwrap();
+
+        let elected = tokio::spawn(election.run(shutdown.clone()));
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        assert_eq!(get_process_by_id(&db, &id).await.unwrap().status, OperationStatus::Outdated);
+
+        shutdown.cancel();
+        task.await.unwrap();
+        elected.await.unwrap();
+    }
+}
@@ -6,6 +6,9 @@ pub type Result<T> = core::result::Result<T, Error>;
 #[derive(Debug, From)]
 pub enum Error {
     Job(String),
+    UnknownTask(String),
+    /// A run of the task is already in progress.
+    TaskRunning(String),
 
     #[from]
     DB(db::error::Error),
@@ -15,6 +18,12 @@ pub enum Error {
 
     #[from]
     Cron(tokio_cron_scheduler::JobSchedulerError),
+
+    #[from]
+    Io(std::io::Error),
+
+    #[from]
+    Json(serde_json::Error),
 }
 
 impl std::fmt::Display for Error {
@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use metrics::gauge;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, instrument, warn};
+
+use crate::db::error::Error;
+use crate::db::repository::{
+    acquire_reserved_process, outdate_expired_holders, renew_process, update_process_status, RESERVED_APP,
+};
+use crate::db::Database;
+use crate::models::OperationStatus;
+
+/// Name of the lock held by the scheduler leader, in the [`RESERVED_APP`].
+pub const LEADER_LOCK: &str = "scheduler";
+
+/// Elects the single instance that runs the scheduled tasks among the
+/// replicas sharing a store.
+///
+/// The leader holds the `flowlocker/scheduler` lock for `lease` and renews it
+/// every third of it. When it dies the lock expires and another instance
+/// takes over; when it shuts down it releases the lock right away.
+#[derive(Debug)]
+pub struct Election {
+    db: Database,
+    lease: Duration,
+    leader: watch::Sender<bool>,
+}
+
+/// Whether this instance currently leads, see [`Election`].
+#[derive(Debug, Clone)]
+pub struct Leadership(watch::Receiver<bool>);
+
+impl Election {
+    pub fn new(db: Database, lease: Duration) -> Self {
+        Election {
+            db,
+            lease,
+            leader: watch::channel(false).0,
+        }
+    }
+
+    pub fn leadership(&self) -> Leadership {
+        Leadership(self.leader.subscribe())
+    }
+
+    #[instrument(skip_all)]
+    pub async fn run(self, shutdown: CancellationToken) {
+        let mut ticks = tokio::time::interval((self.lease / 3).max(Duration::from_millis(100)));
+        let mut held: Option<String> = None;
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = ticks.tick() => {}
+            }
+
+            held = match held {
+                Some(id) => self.renew(id).await,
+                None => self.campaign().await,
+            };
+            self.set_leader(held.is_some());
+        }
+
+        if let Some(id) = held {
+            self.set_leader(false);
+            match update_process_status(&self.db, &id, OperationStatus::Completed).await {
+                Ok(()) => info!(caller = "election", event = "scheduler leadership released"),
+                Err(e) => warn!(caller = "election", event = "can't release scheduler leadership", error = ?e),
+            }
+        }
+    }
+
+    async fn campaign(&self) -> Option<String> {
+        match acquire_reserved_process(&self.db, LEADER_LOCK, self.lease_secs()).await {
+            Ok(id) => {
+                info!(caller = "election", event = "became scheduler leader", lock_id = %id);
+                Some(id)
+            }
+            Err(Error::ProcessExist) => {
+                self.expire_dead_leader().await;
+                None
+            }
+            Err(e) => {
+                warn!(caller = "election", event = "campaign failed", error = ?e);
+                None
+            }
+        }
+    }
+
+    /// A leader that stopped renewing is outdated here rather than by the
+    /// `expiry` task, which only the leader runs.
+    async fn expire_dead_leader(&self) {
+        match outdate_expired_holders(&self.db, RESERVED_APP, LEADER_LOCK).await {
+            Ok(expired) => {
+                for p in expired {
+                    info!(caller = "election", event = "scheduler leader lease expired", lock_id = %p.process_id);
+                }
+            }
+            Err(e) => debug!(caller = "election", event = "can't expire the leader", error = ?e),
+        }
+    }
+
+    /// Any failure steps down: the lease may be gone, and two leaders are
+    /// worse than a short gap.
+    async fn renew(&self, id: String) -> Option<String> {
+        match renew_process(&self.db, &id, self.lease_secs()).await {
+            Ok(()) => Some(id),
+            Err(e) => {
+                warn!(caller = "election", event = "lost scheduler leadership", error = ?e);
+                None
+            }
+        }
+    }
+
+    fn set_leader(&self, leader: bool) {
+        gauge!("flowlocker_scheduler_leader", if leader { 1.0 } else { 0.0 });
+        self.leader.send_if_modified(|current| std::mem::replace(current, leader) != leader);
+    }
+
+    fn lease_secs(&self) -> u64 {
+        self.lease.as_secs().max(1)
+    }
+}
+
+impl Leadership {
+    /// For a single instance that doesn't take part in an election.
+    pub fn always() -> Self {
+        Leadership(watch::channel(true).1)
+    }
+
+    pub fn is_leader(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Resolves once leadership changes, never for [`Leadership::always`].
+    pub async fn changed(&mut self) {
+        if self.0.changed().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::repository::acquire_process;
+
+    async fn wait_for(leadership: &Leadership, leader: bool) {
+        let mut rx = leadership.0.clone();
+        tokio::time::timeout(Duration::from_secs(5), rx.wait_for(|l| *l == leader))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_when_the_leader_goes_away() {
+        let db = Database::in_memory();
+        let lease = Duration::from_secs(1);
+
+        let first = Election::new(db.clone(), lease);
+        let first_leads = first.leadership();
+        let first_stop = CancellationToken::new();
+        let first_task = tokio::spawn(first.run(first_stop.clone()));
+        wait_for(&first_leads, true).await;
+
+        let second = Election::new(db.clone(), lease);
+        let second_leads = second.leadership();
+        let second_task = tokio::spawn(second.run(CancellationToken::new()));
+
+        // Released on shutdown: taken over on the next campaign.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!second_leads.is_leader());
+        first_stop.cancel();
+        first_task.await.unwrap();
+        wait_for(&second_leads, true).await;
+
+        // Dies without releasing: taken over once the lease expired.
+        let third = Election::new(db.clone(), lease);
+        let third_leads = third.leadership();
+        let third_task = tokio::spawn(third.run(CancellationToken::new()));
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!third_leads.is_leader());
+        second_task.abort();
+        wait_for(&third_leads, true).await;
+
+        third_task.abort();
+
+        let res = acquire_process(&db, RESERVED_APP.to_string(), LEADER_LOCK.to_string(), 1).await;
+        assert!(matches!(res, Err(Error::Reserved(_))));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_one_leader_among_concurrent_campaigns() {
+        let db = Database::surreal_in_memory().await;
+
+        let mut campaigns = tokio::task::JoinSet::new();
+        for _ in 0..8 {
+            let election = Election::new(db.clone(), Duration::from_secs(60));
+            campaigns.spawn(async move { election.campaign().await });
+        }
+
+        let mut leaders = 0;
+        while let Some(leader) = campaigns.join_next().await {
+            leaders += leader.unwrap().is_some() as usize;
+        }
+        assert_eq!(leaders, 1);
+    }
+}
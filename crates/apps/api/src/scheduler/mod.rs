@@ -1,57 +1,123 @@
-use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Duration;
-use tokio::runtime::Runtime;
-use tracing::{debug, error, instrument};
-use crate::db::Database;
-use crate::scheduler::cleaner::Cleaner;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use tokio_cron_scheduler::{Job, JobScheduler};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{debug, info, instrument};
+
+use crate::scheduler::registry::Entry;
+
+pub use self::archive::ArchiveExport;
+pub use self::cleaner::{Cleaner, SlaWarnings};
+pub use self::deadlines::Deadlines;
+pub use self::leader::{Election, Leadership};
+pub use self::registry::{Outcome, Registry, TaskRun, TaskStatus};
+pub use self::retention::Retention;
+pub use self::rollup::MetricsRollup;
+pub use self::task::{Action, PlannedChange, Schedule, Task};
 
 pub mod error;
+mod archive;
 mod cleaner;
+mod deadlines;
+mod leader;
+mod registry;
+mod retention;
+mod rollup;
+mod task;
 
 #[derive(Debug, Clone)]
 pub struct Scheduler {
-    cleaner: Arc<Cleaner>,
-    interval: Duration,
+    registry: Registry,
+    leadership: Leadership,
 }
 
 impl Scheduler {
-    pub fn new(mm: Database, interval: Duration) -> Self {
+    pub fn new(registry: Registry) -> Self {
         Scheduler {
-            cleaner: Arc::from(Cleaner::new(mm)),
-            interval,
+            registry,
+            leadership: Leadership::always(),
         }
     }
 
-    #[instrument(skip(self))]
-    pub async fn start(&self) -> error::Result<()> {
-        // tracing::info_span!("scheduler_events");
-        let rt = Arc::new(Mutex::new(Runtime::new().unwrap()));
-
-        let rt_clone = Arc::clone(&rt);
-        let cmd = Arc::clone(&self.cleaner);
-        let interval = self.interval;
-        thread::spawn(move || {
-            let rt = rt_clone.lock().unwrap();
-
-            // Run the tokio interval inside the runtime
-            rt.block_on(async {
-                let mut ticker = tokio::time::interval(interval);
-
-                loop {
-                    ticker.tick().await;
-                    let err = cmd.run().await;
-                    match err {
-                        Err(e) => {
-                            error!("{:?}", e)
-                        }
-                        _ => {
-                            debug!(caller = "scheduler", event = "cleanup cycle completed successfully")
-                        }
-                    }
-                }
-            });
-        });
+    /// Scheduled runs only happen while `leadership` holds, manual ones always do.
+    pub fn leadership(mut self, leadership: Leadership) -> Self {
+        self.leadership = leadership;
+        self
+    }
+
+    /// Runs every registered task on its schedule until `shutdown` is cancelled.
+    ///
+    /// Runs in progress are finished first, shutdown only takes effect
+    /// between runs.
+    #[instrument(skip_all)]
+    pub async fn run(self, shutdown: CancellationToken) -> error::Result<()> {
+        let mut jobs = JobScheduler::new().await?;
+        let tracker = TaskTracker::new();
+
+        for entry in self.registry.entries() {
+            let id = jobs.add(job(entry.clone(), tracker.clone(), self.leadership.clone())?).await?;
+            let _ = entry.job_id.set(id);
+            info!(caller = "scheduler", task = entry.task.name(), schedule = ?entry.task.schedule());
+        }
+
+        jobs.start().await?;
+        self.registry.attach(Some(jobs.clone()));
+        shutdown.cancelled().await;
+
+        self.registry.attach(None);
+        jobs.shutdown().await?;
+        tracker.close();
+        tracker.wait().await;
+
+        info!(caller = "scheduler", event = "scheduler stopped");
         Ok(())
     }
 }
+
+fn job(entry: Arc<Entry>, tracker: TaskTracker, leadership: Leadership) -> error::Result<Job> {
+    let schedule = entry.task.schedule();
+
+    let run = move |_, _| -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        if !leadership.is_leader() {
+            debug!(task = entry.task.name(), event = "task skipped, not the scheduler leader");
+            return Box::pin(async {});
+        }
+
+        let run = tracker.track_future(entry.clone().tick());
+        Box::pin(async move {
+            let _ = run.await;
+        })
+    };
+
+    let job = match schedule {
+        Schedule::Every(interval) => Job::new_repeated_async(interval, run)?,
+        Schedule::Cron(expression) => Job::new_async(expression.as_str(), run)?,
+    };
+
+    Ok(job)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::db::Database;
+
+    #[tokio::test]
+    async fn test_stops_on_shutdown() {
+        let db = Database::in_memory();
+        let registry = Registry::new(Duration::from_secs(30))
+            .register(Cleaner::new(db.clone(), Duration::from_secs(3600), SlaWarnings::default()))
+            .register(ArchiveExport::new(db, std::env::temp_dir(), "0 0 3 * * *"));
+        let shutdown = CancellationToken::new();
+
+        let task = tokio::spawn(Scheduler::new(registry).run(shutdown.clone()));
+        shutdown.cancel();
+
+        tokio::time::timeout(Duration::from_secs(5), task).await.unwrap().unwrap().unwrap();
+    }
+}
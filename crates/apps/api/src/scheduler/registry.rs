@@ -0,0 +1,421 @@
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::time::Instant;
+use tokio_cron_scheduler::JobScheduler;
+use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::scheduler::error::{Error, Result};
+use crate::scheduler::task::{PlannedChange, Schedule, Task, TaskReport};
+
+/// Longest pause after repeated failures of a task.
+const MAX_BACKOFF: Duration = Duration::from_secs(30 * 60);
+
+/// The tasks run by the scheduler.
+///
+/// Each task is isolated from the others: a failing or panicking task only
+/// delays its own next runs, by `backoff` doubled on every consecutive failure.
+#[derive(Clone)]
+pub struct Registry {
+    entries: Vec<Arc<Entry>>,
+    backoff: Duration,
+    dry_run: bool,
+    // Set while the scheduler runs, to look up next runs.
+    jobs: Arc<Mutex<Option<JobScheduler>>>,
+}
+
+pub(super) struct Entry {
+    pub(super) task: Box<dyn Task>,
+    pub(super) job_id: OnceLock<Uuid>,
+    backoff: Duration,
+    dry_run: bool,
+    state: Mutex<EntryState>,
+}
+
+#[derive(Default)]
+struct EntryState {
+    running: bool,
+    failures: u32,
+    retry_at: Option<Instant>,
+    last_run: Option<TaskRun>,
+    last_success: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Succeeded,
+    Failed,
+}
+
+/// One run of a task.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TaskRun {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub outcome: Outcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Processes marked `Outdated`.
+    pub outdated: u64,
+    pub deleted: u64,
+    /// Triggered through the admin API rather than the schedule.
+    pub manual: bool,
+    /// Nothing was changed, `outdated` and `deleted` are what the run would have done.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TaskStatus {
+    #[schema(example = "expiry")]
+    pub name: String,
+    /// `every <n>s` or `cron <expression>`.
+    #[schema(example = "every 30s")]
+    pub schedule: String,
+    pub running: bool,
+    pub consecutive_failures: u32,
+    pub last_run: Option<TaskRun>,
+    pub last_success: Option<DateTime<Utc>>,
+    /// Next scheduled tick, `null` while the scheduler is stopped.
+    pub next_run: Option<DateTime<Utc>>,
+    /// Scheduled ticks are skipped until then after a failure.
+    pub backoff_until: Option<DateTime<Utc>>,
+}
+
+impl Registry {
+    pub fn new(backoff: Duration) -> Self {
+        Registry {
+            entries: Vec::new(),
+            backoff,
+            dry_run: false,
+            jobs: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Tasks that change processes only log what they would change, see
+    /// [`Task::plan`]. Set it before registering the tasks.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Panics when a task with the same name is already registered.
+    pub fn register(mut self, task: impl Task + 'static) -> Self {
+        assert!(
+            self.entries.iter().all(|e| e.task.name() != task.name()),
+            "task {} is registered twice",
+            task.name()
+        );
+
+        self.entries.push(Arc::new(Entry {
+            task: Box::new(task),
+            job_id: OnceLock::new(),
+            backoff: self.backoff,
+            dry_run: self.dry_run,
+            state: Mutex::new(EntryState::default()),
+        }));
+        self
+    }
+
+    pub(super) fn entries(&self) -> &[Arc<Entry>] {
+        &self.entries
+    }
+
+    pub(super) fn attach(&self, jobs: Option<JobScheduler>) {
+        *self.jobs.lock().unwrap_or_else(|e| e.into_inner()) = jobs;
+    }
+
+    pub async fn status(&self) -> Vec<TaskStatus> {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut statuses = Vec::with_capacity(self.entries.len());
+
+        for entry in &self.entries {
+            let next_run = match (jobs.clone(), entry.job_id.get()) {
+                (Some(mut jobs), Some(id)) => jobs.next_tick_for_job(*id).await.ok().flatten(),
+                _ => None,
+            };
+
+            statuses.push(entry.status(next_run));
+        }
+
+        statuses
+    }
+
+    /// Runs `name` right away, even while it's backing off.
+    pub async fn run_now(&self, name: &str) -> Result<TaskRun> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.task.name() == name)
+            .ok_or_else(|| Error::UnknownTask(name.to_string()))?;
+
+        entry
+            .clone()
+            .run(true)
+            .await
+            .ok_or_else(|| Error::TaskRunning(name.to_string()))
+    }
+
+    /// What every task would change right now, regardless of dry-run mode.
+    pub async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        let mut changes = Vec::new();
+        for entry in &self.entries {
+            changes.extend(entry.task.plan().await?.unwrap_or_default());
+        }
+
+        Ok(changes)
+    }
+}
+
+impl std::fmt::Debug for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.entries.iter().map(|e| e.task.name())).finish()
+    }
+}
+
+impl Entry {
+    /// Scheduled run: skipped, returning `None`, while the previous run is
+    /// still going or the task is backing off after a failure.
+    pub(super) async fn tick(self: Arc<Self>) -> Option<TaskRun> {
+        self.run(false).await
+    }
+
+    async fn run(self: Arc<Self>, manual: bool) -> Option<TaskRun> {
+        let name = self.task.name();
+
+        {
+            let mut state = self.lock_state();
+            if state.running {
+                debug!(task = name, event = "task skipped, previous run still in progress");
+                return None;
+            }
+            if !manual && state.retry_at.is_some_and(|at| Instant::now() < at) {
+                debug!(task = name, event = "task skipped, backing off");
+                return None;
+            }
+            state.running = true;
+        }
+
+        // Spawned as a whole, the run is recorded and `running` reset even
+        // when the caller goes away, e.g. an admin client disconnecting.
+        tokio::spawn(self.record(manual)).await.ok()
+    }
+
+    async fn record(self: Arc<Self>, manual: bool) -> TaskRun {
+        let name = self.task.name();
+        let started_at = Utc::now();
+        let started = Instant::now();
+
+        // A panic is confined to this spawned run.
+        let entry = self.clone();
+        let res = match tokio::spawn(async move { entry.execute().await }).await {
+            Ok(res) => res,
+            Err(e) => Err(Error::Job(format!("task {name} panicked: {e}"))),
+        };
+
+        let report = res.as_ref().copied().unwrap_or_default();
+        let run = TaskRun {
+            started_at,
+            ended_at: Utc::now(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            outcome: if res.is_ok() { Outcome::Succeeded } else { Outcome::Failed },
+            error: res.as_ref().err().map(ToString::to_string),
+            outdated: report.outdated,
+            deleted: report.deleted,
+            manual,
+            dry_run: self.dry_run,
+        };
+
+        let mut state = self.lock_state();
+        state.running = false;
+        state.last_run = Some(run.clone());
+
+        match &res {
+            Ok(report) => {
+                if state.failures > 0 {
+                    info!(task = name, event = "task recovered", failures = state.failures);
+                }
+                state.failures = 0;
+                state.retry_at = None;
+                state.last_success = Some(run.ended_at);
+                debug!(task = name, event = "task completed", outdated = report.outdated, deleted = report.deleted);
+            }
+            Err(e) => {
+                state.failures += 1;
+                let delay = backoff_delay(self.backoff, state.failures);
+                state.retry_at = Some(Instant::now() + delay);
+                error!(task = name, event = "task failed", failures = state.failures, retry_in = ?delay, error = ?e);
+            }
+        }
+
+        run
+    }
+
+    async fn execute(&self) -> Result<TaskReport> {
+        if !self.dry_run {
+            return self.task.run().await;
+        }
+
+        let Some(changes) = self.task.plan().await? else {
+            return self.task.run().await;
+        };
+        for change in &changes {
+            info!(
+                task = self.task.name(),
+                event = "dry run",
+                action = ?change.action,
+                process_id = %change.process.process_id,
+                app = %change.process.app,
+                process_name = %change.process.process_name
+            );
+        }
+
+        Ok(TaskReport::planned(&changes))
+    }
+
+    fn status(&self, next_run: Option<DateTime<Utc>>) -> TaskStatus {
+        let state = self.lock_state();
+        let now = Instant::now();
+
+        TaskStatus {
+            name: self.task.name().to_string(),
+            schedule: match self.task.schedule() {
+                Schedule::Every(interval) => format!("every {}s", interval.as_secs()),
+                Schedule::Cron(expression) => format!("cron {expression}"),
+            },
+            running: state.running,
+            consecutive_failures: state.failures,
+            last_run: state.last_run.clone(),
+            last_success: state.last_success,
+            next_run,
+            backoff_until: state
+                .retry_at
+                .filter(|at| *at > now)
+                .and_then(|at| chrono::Duration::from_std(at - now).ok())
+                .map(|left| Utc::now() + left),
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, EntryState> {
+        self.state.lock().unwrap_or_else(|e| {
+            warn!("scheduler task state is poisoned");
+            e.into_inner()
+        })
+    }
+}
+
+/// `backoff` doubled for every failure after the first one, capped at [`MAX_BACKOFF`].
+fn backoff_delay(backoff: Duration, failures: u32) -> Duration {
+    backoff
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use async_trait::async_trait;
+
+    use super::*;
+
+    struct Flaky {
+        runs: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl Task for Flaky {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        fn schedule(&self) -> Schedule {
+            Schedule::Every(Duration::from_secs(1))
+        }
+
+        async fn run(&self) -> Result<TaskReport> {
+            match self.runs.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(Error::Job("first run fails".to_string())),
+                1 => panic!("second run panics"),
+                _ => Ok(TaskReport { outdated: 2, deleted: 1 }),
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failures_back_off_without_stopping_the_task() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let registry = Registry::new(Duration::from_secs(10)).register(Flaky { runs: runs.clone() });
+        let entry = registry.entries()[0].clone();
+
+        assert_eq!(entry.clone().tick().await.unwrap().outcome, Outcome::Failed);
+        // Backing off: the tick is skipped.
+        assert!(entry.clone().tick().await.is_none());
+        assert!(registry.status().await[0].backoff_until.is_some());
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        let run = entry.clone().tick().await.unwrap();
+        assert_eq!(run.outcome, Outcome::Failed);
+        assert!(run.error.unwrap().contains("panicked"));
+
+        // Two failures in a row: the pause doubled, but a manual run goes through.
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert!(entry.clone().tick().await.is_none());
+
+        let run = registry.run_now("flaky").await.unwrap();
+        assert_eq!((run.outcome, run.outdated, run.deleted, run.manual), (Outcome::Succeeded, 2, 1, true));
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+        let status = &registry.status().await[0];
+        assert_eq!(status.consecutive_failures, 0);
+        assert!(status.last_success.is_some());
+        assert!(status.backoff_until.is_none());
+
+        assert!(matches!(registry.run_now("nope").await, Err(Error::UnknownTask(_))));
+    }
+
+    struct Slow;
+
+    #[async_trait]
+    impl Task for Slow {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        fn schedule(&self) -> Schedule {
+            Schedule::Every(Duration::from_secs(1))
+        }
+
+        async fn run(&self) -> Result<TaskReport> {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(TaskReport::default())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_outlives_its_caller() {
+        let registry = Registry::new(Duration::from_secs(10)).register(Slow);
+
+        let dropped = tokio::time::timeout(Duration::from_secs(1), registry.run_now("slow")).await;
+        assert!(dropped.is_err());
+        assert!(registry.status().await[0].running);
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        let status = &registry.status().await[0];
+        assert!(!status.running);
+        assert!(status.last_run.is_some());
+        assert!(registry.run_now("slow").await.is_ok());
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        assert_eq!(backoff_delay(Duration::from_secs(30), 1), Duration::from_secs(30));
+        assert_eq!(backoff_delay(Duration::from_secs(30), 3), Duration::from_secs(120));
+        assert_eq!(backoff_delay(Duration::from_secs(30), 40), MAX_BACKOFF);
+    }
+}
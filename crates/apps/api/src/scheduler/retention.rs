@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tracing::{info, instrument};
+
+use crate::db::repository::{delete_retired_processes, get_retired_processes};
+use crate::db::Database;
+use crate::scheduler::error::Result;
+use crate::scheduler::task::{Action, PlannedChange, Schedule, Task, TaskReport};
+use crate::time;
+
+/// The `retention` task: deletes finished processes once they've been
+/// finished for longer than `retention`.
+#[derive(Debug)]
+pub struct Retention {
+    db: Database,
+    interval: Duration,
+    retention: Duration,
+}
+
+impl Retention {
+    pub fn new(db: Database, interval: Duration, retention: Duration) -> Self {
+        Retention { db, interval, retention }
+    }
+
+    #[instrument(skip(self))]
+    async fn purge(&self) -> Result<TaskReport> {
+        let deleted = delete_retired_processes(&self.db, self.cutoff()?).await?;
+        for p in &deleted {
+            info!(name = "process deleted", process_id = %p.process_id);
+        }
+
+        Ok(TaskReport {
+            deleted: deleted.len() as u64,
+            ..TaskReport::default()
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        Ok(get_retired_processes(&self.db, self.cutoff()?)
+            .await?
+            .into_iter()
+            .map(|p| PlannedChange {
+                task: self.name().to_string(),
+                action: Action::Delete,
+                process: p.to_response(),
+            })
+            .collect())
+    }
+
+    /// Processes finished and last updated before this are deleted.
+    fn cutoff(&self) -> Result<u64> {
+        Ok(time::from_epoch()?.saturating_sub(self.retention.as_secs()))
+    }
+}
+
+#[async_trait]
+impl Task for Retention {
+    fn name(&self) -> &'static str {
+        "retention"
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(self.interval)
+    }
+
+    async fn run(&self) -> Result<TaskReport> {
+        self.purge().await
+    }
+
+    async fn plan(&self) -> Result<Option<Vec<PlannedChange>>> {
+        Ok(Some(Retention::plan(self).await?))
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use metrics::gauge;
+use tracing::instrument;
+
+use crate::db::repository::count_processes;
+use crate::db::Database;
+use crate::scheduler::error::Result;
+use crate::scheduler::task::{Schedule, Task, TaskReport};
+
+/// The `metrics_rollup` task: publishes the `flowlocker_locks` gauge, the
+/// number of stored locks per app and status.
+#[derive(Debug)]
+pub struct MetricsRollup {
+    db: Database,
+    interval: Duration,
+    // Label pairs published by the previous run, reset to zero once they're gone.
+    published: Mutex<HashSet<(String, String)>>,
+}
+
+impl MetricsRollup {
+    pub fn new(db: Database, interval: Duration) -> Self {
+        MetricsRollup {
+            db,
+            interval,
+            published: Mutex::new(HashSet::new()),
+        }
+    }
+
+    #[instrument(skip(self))]
+    async fn rollup(&self) -> Result<TaskReport> {
+        let counts: HashMap<(String, String), u64> = count_processes(&self.db)
+            .await?
+            .into_iter()
+            .map(|c| ((c.app, c.status.to_string()), c.count))
+            .collect();
+
+        let mut published = self.published.lock().unwrap_or_else(|e| e.into_inner());
+        for (app, status) in published.drain() {
+            if !counts.contains_key(&(app.clone(), status.clone())) {
+                gauge!("flowlocker_locks", 0.0, "app" => app, "status" => status);
+            }
+        }
+        for ((app, status), count) in &counts {
+            gauge!("flowlocker_locks", *count as f64, "app" => app.clone(), "status" => status.clone());
+        }
+        published.extend(counts.into_keys());
+
+        Ok(TaskReport::default())
+    }
+}
+
+#[async_trait]
+impl Task for MetricsRollup {
+    fn name(&self) -> &'static str {
+        "metrics_rollup"
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(self.interval)
+    }
+
+    async fn run(&self) -> Result<TaskReport> {
+        self.rollup().await
+    }
+}
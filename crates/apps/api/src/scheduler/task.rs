@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::models::ResponseProcess;
+use crate::scheduler::error::Result;
+
+/// Background job run by the [`Scheduler`](super::Scheduler).
+#[async_trait]
+pub trait Task: Send + Sync {
+    /// Unique, used in logs and to address the task.
+    fn name(&self) -> &'static str;
+
+    fn schedule(&self) -> Schedule;
+
+    async fn run(&self) -> Result<TaskReport>;
+
+    /// The changes `run` would make to processes right now, without making
+    /// them. `None` for tasks that don't change processes.
+    async fn plan(&self) -> Result<Option<Vec<PlannedChange>>> {
+        Ok(None)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    /// Every `Duration`, starting one period after the scheduler starts.
+    Every(Duration),
+    /// A cron expression with seconds, e.g. `0 */5 * * * *`, evaluated in UTC.
+    Cron(String),
+}
+
+/// What a run changed, tasks leave the counters they don't touch at zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TaskReport {
+    pub outdated: u64,
+    pub deleted: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Marked `Outdated`.
+    Outdate,
+    Delete,
+}
+
+/// A change a dry run found, see [`Task::plan`].
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PlannedChange {
+    #[schema(example = "expiry")]
+    pub task: String,
+    pub action: Action,
+    pub process: ResponseProcess,
+}
+
+impl TaskReport {
+    /// What `plan` would have changed, as if it ran.
+    pub(super) fn planned(changes: &[PlannedChange]) -> Self {
+        let count = |action| changes.iter().filter(|c| c.action == action).count() as u64;
+
+        TaskReport {
+            outdated: count(Action::Outdate),
+            deleted: count(Action::Delete),
+        }
+    }
+}
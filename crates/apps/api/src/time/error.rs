@@ -6,4 +6,5 @@ pub type Result<T> = core::result::Result<T, Error>;
 #[derive(Debug, Serialize, From)]
 pub enum Error {
     TimeConversion,
+    InvalidDuration(String),
 }
@@ -21,6 +21,31 @@ pub fn to_string_time(t: u64) -> String {
     format
 }
 
+/// parse_duration accept data in string format and returns seconds
+/// #### Seconds
+/// "60s"
+/// #### Minutes
+/// "5m"
+/// #### Hours
+/// "2h"
+pub fn parse_duration(duration: &str) -> Result<u64> {
+    let invalid = || Error::InvalidDuration(duration.to_string());
+
+    if duration.is_empty() || !duration.is_char_boundary(duration.len() - 1) {
+        return Err(invalid());
+    }
+
+    let (value, unit) = duration.split_at(duration.len() - 1);
+    let value = value.parse::<u64>().map_err(|_| invalid())?;
+
+    match unit {
+        "s" => Ok(value),
+        "m" => Ok(value * 60),
+        "h" => Ok(value * 60 * 60),
+        _ => Err(invalid()),
+    }
+}
+
 pub fn from_epoch() -> Result<u64> {
     match UNIX_EPOCH.elapsed() {
         Ok(time) => Ok(to_u64(time)),
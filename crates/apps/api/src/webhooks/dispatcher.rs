@@ -0,0 +1,279 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::db::webhooks::{get_webhooks, insert_dead_letter};
+use crate::db::Database;
+use crate::models::{DeadLetter, Process, ResponseProcess, Webhook, WebhookEvent};
+use crate::time::from_epoch;
+
+use super::{sign, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    /// Delay before the second attempt, doubled for every following one.
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    fn delay(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(MAX_BACKOFF)
+    }
+}
+
+/// Posts lock events to the matching webhook subscriptions.
+#[derive(Debug, Clone)]
+pub struct Dispatcher {
+    db: Database,
+    client: reqwest::Client,
+    retry: RetryPolicy,
+}
+
+#[derive(Debug, Serialize)]
+struct Payload<'a> {
+    delivery_id: &'a str,
+    event: WebhookEvent,
+    occurred_at: DateTime<Utc>,
+    lock: ResponseProcess,
+    /// Percent of the SLA consumed, only on `sla_warning`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sla_consumed: Option<u64>,
+}
+
+impl Dispatcher {
+    pub fn new(db: Database, retry: RetryPolicy) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Dispatcher { db, client, retry }
+    }
+
+    /// Subscribes to lock events right away, so nothing published after this
+    /// call is missed, and delivers them in the background.
+    pub fn spawn(self) -> JoinHandle<()> {
+        let events = self.db.subscribe();
+        tokio::spawn(self.run(events))
+    }
+
+    async fn run(self, mut events: Receiver<Process>) {
+        loop {
+            match events.recv().await {
+                Ok(p) => self.dispatch(p).await,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(name = "webhook_events_lagged", skipped);
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    async fn dispatch(&self, p: Process) {
+        if let Some(event) = WebhookEvent::for_status(&p.status) {
+            self.notify(event, &p, None).await;
+        }
+    }
+
+    /// Delivers `event` about `p` to every matching subscription in the background.
+    pub async fn notify(&self, event: WebhookEvent, p: &Process, sla_consumed: Option<u64>) {
+        let webhooks = match get_webhooks(&self.db).await {
+            Ok(webhooks) => webhooks,
+            Err(e) => {
+                error!("can't load webhooks for process {}: {:?}", p.process_id, e);
+                return;
+            }
+        };
+
+        let occurred_at = Utc::now();
+
+        for webhook in webhooks.into_iter().filter(|w| w.matches(event, p)) {
+            let delivery_id = Uuid::now_v7().to_string();
+            let payload = Payload {
+                delivery_id: &delivery_id,
+                event,
+                occurred_at,
+                lock: p.to_response(),
+                sla_consumed,
+            };
+            let body = serde_json::to_string(&payload).unwrap_or_default();
+
+            tokio::spawn(self.clone().deliver(webhook, event, delivery_id, body));
+        }
+    }
+
+    #[instrument(skip(self, webhook, body), fields(webhook_id = %webhook.webhook_id))]
+    async fn deliver(self, webhook: Webhook, event: WebhookEvent, delivery_id: String, body: String) {
+        let mut last_error = String::new();
+
+        for attempt in 1..=self.retry.max_attempts {
+            match self.post(&webhook, event, &delivery_id, &body).await {
+                Ok(()) => {
+                    debug!(name = "webhook_delivered", attempt);
+                    return;
+                }
+                Err(e) => {
+                    warn!(name = "webhook_delivery_failed", attempt, error = %e);
+                    last_error = e;
+                }
+            }
+
+            if attempt < self.retry.max_attempts {
+                tokio::time::sleep(self.retry.delay(attempt)).await;
+            }
+        }
+
+        info!(name = "webhook_dead_lettered", attempts = self.retry.max_attempts);
+
+        let letter = DeadLetter {
+            delivery_id,
+            webhook_id: webhook.webhook_id,
+            url: webhook.url,
+            event,
+            payload: body,
+            attempts: self.retry.max_attempts,
+            last_error,
+            create_at: from_epoch().unwrap_or_default(),
+        };
+
+        if let Err(e) = insert_dead_letter(&self.db, letter).await {
+            error!("can't store dead letter: {:?}", e);
+        }
+    }
+
+    async fn post(&self, webhook: &Webhook, event: WebhookEvent, delivery_id: &str, body: &str) -> Result<(), String> {
+        // Signed per attempt so receivers can keep a tight timestamp tolerance.
+        let timestamp = from_epoch().map_err(|e| format!("{e:?}"))?;
+
+        let res = self
+            .client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, sign(&webhook.secret, timestamp, body))
+            .header(TIMESTAMP_HEADER, timestamp)
+            .header(EVENT_HEADER, event.to_string())
+            .header(DELIVERY_HEADER, delivery_id)
+            .body(body.to_string())
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if !res.status().is_success() {
+            return Err(format!("receiver answered {}", res.status()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::db::repository::{acquire_process, update_process_status};
+    use crate::db::webhooks::{create_webhook, get_dead_letters};
+    use crate::models::OperationStatus;
+
+    /// Local receiver answering every POST with `status` and forwarding it to the test.
+    async fn stub_receiver(status: StatusCode) -> (String, mpsc::UnboundedReceiver<(HeaderMap, String)>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(move |State(tx): State<mpsc::UnboundedSender<(HeaderMap, String)>>, headers: HeaderMap, body: String| async move {
+                    let _ = tx.send((headers, body));
+                    status
+                }),
+            )
+            .with_state(tx);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        (url, rx)
+    }
+
+    fn retry(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            backoff: Duration::from_millis(10),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delivers_signed_event() {
+        let db = Database::in_memory();
+        let (url, mut received) = stub_receiver(StatusCode::NO_CONTENT).await;
+
+        create_webhook(&db, url, "s3cret".to_string(), Some("billing".to_string()), None, vec![WebhookEvent::Acquired])
+            .await
+            .unwrap();
+        Dispatcher::new(db.clone(), retry(1)).spawn();
+
+        acquire_process(&db, "reports".to_string(), "daily".to_string(), 60).await.unwrap();
+        acquire_process(&db, "billing".to_string(), "invoices".to_string(), 60).await.unwrap();
+
+        let (headers, body) = received.recv().await.unwrap();
+        let timestamp: u64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+
+        assert_eq!(headers[SIGNATURE_HEADER], sign("s3cret", timestamp, &body));
+        assert_eq!(headers[EVENT_HEADER], "acquired");
+
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["event"], "acquired");
+        assert_eq!(body["lock"]["app"], "billing");
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_after_last_retry() {
+        let db = Database::in_memory();
+        let (url, mut received) = stub_receiver(StatusCode::INTERNAL_SERVER_ERROR).await;
+
+        let webhook = create_webhook(&db, url, "s3cret".to_string(), None, None, WebhookEvent::ALL.to_vec())
+            .await
+            .unwrap();
+        Dispatcher::new(db.clone(), retry(3)).spawn();
+
+        let id = acquire_process(&db, "billing".to_string(), "invoices".to_string(), 60).await.unwrap();
+        update_process_status(&db, &id, OperationStatus::InProgress).await.unwrap();
+
+        for _ in 0..3 {
+            received.recv().await.unwrap();
+        }
+
+        let mut letters = Vec::new();
+        for _ in 0..50 {
+            letters = get_dead_letters(&db).await.unwrap();
+            if !letters.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].webhook_id, webhook.webhook_id);
+        assert_eq!(letters[0].event, WebhookEvent::Acquired);
+        assert_eq!(letters[0].attempts, 3);
+        // `InProgress` is not announced.
+        assert!(received.try_recv().is_err());
+    }
+}
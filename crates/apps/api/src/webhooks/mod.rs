@@ -0,0 +1,20 @@
+mod dispatcher;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub use self::dispatcher::{Dispatcher, RetryPolicy};
+
+pub const SIGNATURE_HEADER: &str = "X-Flowlocker-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Flowlocker-Timestamp";
+pub const EVENT_HEADER: &str = "X-Flowlocker-Event";
+pub const DELIVERY_HEADER: &str = "X-Flowlocker-Delivery";
+
+/// `sha256=<hex HMAC-SHA256 of "<timestamp>.<body>">`. Receivers recompute it
+/// with the subscription secret and reject stale timestamps to stop replays.
+pub fn sign(secret: &str, timestamp: u64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{timestamp}.{body}").as_bytes());
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
@@ -0,0 +1,42 @@
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Client(flowlocker_client::error::Error),
+    /// `force-unlock` found nothing to cancel.
+    NoActiveLock { app: String, process: String },
+    Spawn(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl Error {
+    /// The lock is held by someone else and we didn't wait for it.
+    pub fn is_locked(&self) -> bool {
+        matches!(self, Error::Client(flowlocker_client::error::Error::Locked))
+    }
+}
+
+impl From<flowlocker_client::error::Error> for Error {
+    fn from(err: flowlocker_client::error::Error) -> Self {
+        Error::Client(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Client(e) => write!(f, "{e}"),
+            Error::NoActiveLock { app, process } => write!(f, "no active lock for {app}/{process}"),
+            Error::Spawn(e) => write!(f, "can't run command: {e}"),
+            Error::Json(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
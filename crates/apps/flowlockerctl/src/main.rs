@@ -0,0 +1,220 @@
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use flowlocker_client::{Client, ListLocks, OperationStatus};
+
+use crate::error::{Error, Result};
+use crate::output::{print_change, print_lock, print_locks, Format};
+
+mod error;
+mod output;
+mod run;
+
+/// Exit code when the lock is held and `--wait` wasn't given.
+const EXIT_LOCKED: u8 = 3;
+
+#[derive(Debug, Parser)]
+#[command(name = "flowlockerctl", version, about = "Inspect, take and release flowlocker locks")]
+struct Cli {
+    /// Server root url.
+    #[arg(long, global = true, env = "FLOWLOCKER_URL", default_value = "http://localhost:8050")]
+    server: String,
+
+    #[arg(long, short, global = true, value_enum, default_value_t = Format::Table)]
+    output: Format,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// List locks.
+    List {
+        #[arg(long)]
+        app: Option<String>,
+        #[arg(long)]
+        process: Option<String>,
+        /// One or more comma separated statuses.
+        #[arg(long, value_delimiter = ',')]
+        status: Vec<OperationStatus>,
+        #[arg(long)]
+        limit: Option<u32>,
+        /// `next_cursor` of the previous page.
+        #[arg(long)]
+        cursor: Option<String>,
+        /// Follow cursors until the last page.
+        #[arg(long, conflicts_with = "cursor")]
+        all: bool,
+    },
+    /// Show one lock.
+    Get { lock_id: String },
+    /// Take a lock and leave it held, release it with `release`.
+    Acquire {
+        app: String,
+        process: String,
+        #[arg(long, default_value = "60s", value_parser = parse_eta)]
+        eta: Duration,
+        /// Wait for a held lock instead of failing.
+        #[arg(long)]
+        wait: bool,
+    },
+    /// Mark a lock `Completed`.
+    Release { lock_id: String },
+    /// Mark a lock `Canceled`.
+    Cancel { lock_id: String },
+    /// Cancel whatever lock is held for an app and process.
+    ForceUnlock { app: String, process: String },
+    /// Print every status change of a lock until it's finished.
+    Watch { lock_id: String },
+    /// Run a command while holding the lock: `run billing invoices -- ./invoices.sh`.
+    Run {
+        app: String,
+        process: String,
+        #[arg(long, default_value = "60s", value_parser = parse_eta)]
+        eta: Duration,
+        /// Fail right away when the lock is held instead of waiting.
+        #[arg(long)]
+        no_wait: bool,
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match execute(cli).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e}");
+            if e.is_locked() {
+                ExitCode::from(EXIT_LOCKED)
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+    }
+}
+
+async fn execute(cli: Cli) -> Result<ExitCode> {
+    let client = Client::new(&cli.server)?;
+    let format = cli.output;
+
+    match cli.command {
+        Command::List { app, process, status, limit, cursor, all } => {
+            let mut filter = ListLocks { app, process, statuses: status, cursor, limit };
+
+            if !all {
+                let page = client.list(&filter).await?;
+                print_locks(format, &page.locks, page.next_cursor.as_deref())?;
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            let mut locks = Vec::new();
+            loop {
+                let page = client.list(&filter).await?;
+                locks.extend(page.locks);
+
+                match page.next_cursor {
+                    Some(cursor) => filter.cursor = Some(cursor),
+                    None => break,
+                }
+            }
+            print_locks(format, &locks, None)?;
+        }
+        Command::Get { lock_id } => print_lock(format, &client.get(&lock_id).await?)?,
+        Command::Acquire { app, process, eta, wait } => {
+            let guard = if wait {
+                client.acquire(&app, &process, eta).await?
+            } else {
+                client
+                    .try_acquire(&app, &process, eta)
+                    .await?
+                    .ok_or(flowlocker_client::error::Error::Locked)?
+            };
+            print_lock(format, &guard.detach())?;
+        }
+        Command::Release { lock_id } => print_lock(format, &client.release(&lock_id).await?)?,
+        Command::Cancel { lock_id } => {
+            print_lock(format, &client.set_status(&lock_id, OperationStatus::Canceled).await?)?
+        }
+        Command::ForceUnlock { app, process } => {
+            let filter = ListLocks {
+                app: Some(app.clone()),
+                process: Some(process.clone()),
+                statuses: vec![OperationStatus::New, OperationStatus::InProgress],
+                ..Default::default()
+            };
+
+            let held = client.list(&filter).await?.locks;
+            if held.is_empty() {
+                return Err(Error::NoActiveLock { app, process });
+            }
+
+            let mut canceled = Vec::with_capacity(held.len());
+            for lock in held {
+                canceled.push(client.set_status(&lock.process_id, OperationStatus::Canceled).await?);
+            }
+            print_locks(format, &canceled, None)?;
+        }
+        Command::Watch { lock_id } => {
+            let mut watch = client.watch(&lock_id).await?;
+            while let Some(lock) = watch.next().await? {
+                print_change(format, &lock)?;
+            }
+        }
+        Command::Run { app, process, eta, no_wait, command } => {
+            return run::run(&client, &app, &process, eta, !no_wait, &command).await;
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Same format as the server: `<n>s`, `<n>m` or `<n>h`.
+fn parse_eta(eta: &str) -> core::result::Result<Duration, String> {
+    let invalid = || format!("invalid eta `{eta}`, expected <n>s, <n>m or <n>h");
+
+    let unit = eta.chars().last().ok_or_else(invalid)?;
+    let value: u64 = eta[..eta.len() - unit.len_utf8()].parse().map_err(|_| invalid())?;
+
+    let secs = match unit {
+        's' => Some(value),
+        'm' => value.checked_mul(60),
+        'h' => value.checked_mul(60 * 60),
+        _ => return Err(invalid()),
+    };
+
+    match secs {
+        Some(secs) if secs > 0 => Ok(Duration::from_secs(secs)),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::parse_from(["flowlockerctl", "run", "billing", "invoices", "--eta", "5m", "--", "sh", "-c", "exit 0"]);
+        assert!(matches!(
+            cli.command,
+            Command::Run { eta, no_wait: false, ref command, .. } if eta == Duration::from_secs(300) && command.len() == 3
+        ));
+
+        assert_eq!(parse_eta("2h"), Ok(Duration::from_secs(7200)));
+        assert!(parse_eta("10").is_err());
+        assert!(parse_eta("").is_err());
+        assert_eq!(parse_eta("0m"), Err("invalid eta `0m`, expected <n>s, <n>m or <n>h".to_string()));
+        assert!(parse_eta(&format!("{}h", u64::MAX / 60)).is_err());
+        assert!(parse_eta(&format!("{}m", u64::MAX)).is_err());
+    }
+}
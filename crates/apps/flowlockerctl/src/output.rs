@@ -0,0 +1,128 @@
+use clap::ValueEnum;
+use flowlocker_client::ResponseProcess;
+use serde::Serialize;
+
+use crate::error::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    Table,
+    Json,
+}
+
+const HEADER: [&str; 7] = ["ID", "APP", "PROCESS", "STATUS", "CREATED", "UPDATED", "SLA"];
+
+#[derive(Serialize)]
+struct LockList<'a> {
+    locks: &'a [ResponseProcess],
+    next_cursor: Option<&'a str>,
+}
+
+pub fn print_lock(format: Format, lock: &ResponseProcess) -> Result<()> {
+    match format {
+        Format::Table => print!("{}", table(std::slice::from_ref(lock))),
+        Format::Json => println!("{}", serde_json::to_string_pretty(lock)?),
+    }
+
+    Ok(())
+}
+
+pub fn print_locks(format: Format, locks: &[ResponseProcess], next_cursor: Option<&str>) -> Result<()> {
+    match format {
+        Format::Table => {
+            print!("{}", table(locks));
+            if let Some(cursor) = next_cursor {
+                eprintln!("more locks: --cursor {cursor}");
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&LockList { locks, next_cursor })?),
+    }
+
+    Ok(())
+}
+
+/// One line per status change, JSON lines with `--output json`.
+pub fn print_change(format: Format, lock: &ResponseProcess) -> Result<()> {
+    match format {
+        Format::Table => println!("{}  {}", timestamp(&lock.updated_at), lock.status),
+        Format::Json => println!("{}", serde_json::to_string(lock)?),
+    }
+
+    Ok(())
+}
+
+fn table(locks: &[ResponseProcess]) -> String {
+    let rows: Vec<[String; 7]> = locks
+        .iter()
+        .map(|l| {
+            [
+                l.process_id.to_string(),
+                l.app.to_string(),
+                l.process_name.to_string(),
+                l.status.to_string(),
+                timestamp(&l.create_at),
+                timestamp(&l.updated_at),
+                format!("{}s", l.sla),
+            ]
+        })
+        .collect();
+
+    let mut widths = HEADER.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut out = String::new();
+    let header = HEADER.map(str::to_string);
+    for row in std::iter::once(&header).chain(&rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        out.push_str(line.join("  ").trim_end());
+        out.push('\n');
+    }
+
+    out
+}
+
+fn timestamp(t: &chrono::DateTime<chrono::Utc>) -> String {
+    t.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use flowlocker_client::OperationStatus;
+
+    use super::*;
+
+    #[test]
+    fn test_table_aligns_columns() {
+        let lock = ResponseProcess {
+            process_id: "0190f7a4-5b8e-7c1a-9d3e-2f4b6a8c0e12".into(),
+            app: "billing".into(),
+            process_name: "invoices".into(),
+            status: OperationStatus::InProgress,
+            create_at: Utc.with_ymd_and_hms(2024, 7, 1, 10, 0, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2024, 7, 1, 10, 0, 5).unwrap(),
+            ended_at: None,
+            sla: 60,
+        };
+
+        let out = table(&[lock]);
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "ID                                    APP      PROCESS   STATUS      CREATED              UPDATED              SLA"
+        );
+        assert_eq!(
+            lines[1],
+            "0190f7a4-5b8e-7c1a-9d3e-2f4b6a8c0e12  billing  invoices  InProgress  2024-07-01 10:00:00  2024-07-01 10:00:05  60s"
+        );
+    }
+}
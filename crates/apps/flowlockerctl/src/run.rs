@@ -0,0 +1,67 @@
+use std::process::{ExitCode, ExitStatus};
+use std::time::Duration;
+
+use flowlocker_client::error::Error as ClientError;
+use flowlocker_client::{Client, OperationStatus};
+use tokio::process::Command;
+
+use crate::error::{Error, Result};
+
+/// Exit code of a command stopped by Ctrl-C, as shells report it.
+const EXIT_INTERRUPTED: u8 = 130;
+
+/// Runs `command` while holding the lock.
+///
+/// The lock is heartbeated while the command runs, released when it exits
+/// successfully and canceled when it fails, can't start or is interrupted.
+/// The command's exit code is passed through.
+pub async fn run(client: &Client, app: &str, process: &str, eta: Duration, wait: bool, command: &[String]) -> Result<ExitCode> {
+    let mut guard = if wait {
+        client.acquire(app, process, eta).await?
+    } else {
+        client.try_acquire(app, process, eta).await?.ok_or(ClientError::Locked)?
+    };
+    eprintln!("acquired lock {}", guard.id());
+
+    let (program, args) = command.split_first().expect("clap requires a command");
+    let mut child = match Command::new(program).args(args).kill_on_drop(true).spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            guard.set_status(OperationStatus::Canceled).await?;
+            return Err(Error::Spawn(e));
+        }
+    };
+
+    tokio::select! {
+        status = child.wait() => {
+            let status = match status {
+                Ok(status) => status,
+                Err(e) => {
+                    guard.set_status(OperationStatus::Canceled).await?;
+                    return Err(Error::Spawn(e));
+                }
+            };
+
+            if status.success() {
+                guard.release().await?;
+            } else {
+                guard.set_status(OperationStatus::Canceled).await?;
+            }
+
+            Ok(exit_code(status))
+        }
+        _ = tokio::signal::ctrl_c() => {
+            let _ = child.kill().await;
+            guard.set_status(OperationStatus::Canceled).await?;
+
+            Ok(ExitCode::from(EXIT_INTERRUPTED))
+        }
+    }
+}
+
+fn exit_code(status: ExitStatus) -> ExitCode {
+    match status.code() {
+        Some(code) => ExitCode::from(code as u8),
+        None => ExitCode::FAILURE,
+    }
+}
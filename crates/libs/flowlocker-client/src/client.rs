@@ -0,0 +1,345 @@
+use std::time::Duration;
+
+use lib_models::lock::{Heartbeat, NewProcess, OperationStatus, ResponseProcess, UpdateProcess};
+use reqwest::{Method, RequestBuilder, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::guard::LockGuard;
+use crate::watch::Watch;
+
+/// How long [`Client::acquire`] waits before asking again for a held lock.
+pub const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: Url,
+    retry_interval: Duration,
+}
+
+/// Filter for [`Client::list`], every field is optional.
+#[derive(Debug, Clone, Default)]
+pub struct ListLocks {
+    pub app: Option<String>,
+    pub process: Option<String>,
+    pub statuses: Vec<OperationStatus>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug)]
+pub struct Page {
+    pub locks: Vec<ResponseProcess>,
+    /// `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Envelope<T> {
+    data: T,
+    #[serde(default)]
+    meta: Option<Meta>,
+}
+
+#[derive(Deserialize)]
+struct Meta {
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorEnvelope {
+    error: ErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetail {
+    code: String,
+    message: String,
+}
+
+impl Client {
+    /// `base_url` is the server root, e.g. `http://localhost:8050` or
+    /// `http://gateway/flowlocker` behind a path prefix.
+    pub fn new(base_url: &str) -> Result<Self> {
+        let mut base_url = Url::parse(base_url).map_err(|_| Error::InvalidUrl(base_url.to_string()))?;
+        // Request paths are relative, joined they keep the prefix only below a `/`.
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        Ok(Client {
+            http: reqwest::Client::new(),
+            base_url,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+        })
+    }
+
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Waits until the lock is free, then takes it.
+    pub async fn acquire(&self, app: &str, process: &str, eta: Duration) -> Result<LockGuard> {
+        loop {
+            if let Some(guard) = self.try_acquire(app, process, eta).await? {
+                return Ok(guard);
+            }
+
+            tokio::time::sleep(self.retry_interval).await;
+        }
+    }
+
+    /// Takes the lock, or returns `None` right away when it's held.
+    pub async fn try_acquire(&self, app: &str, process: &str, eta: Duration) -> Result<Option<LockGuard>> {
+        let body = NewProcess {
+            app: app.to_string(),
+            process: process.to_string(),
+            eta: format_eta(eta),
+            dedupe_key: None,
+            window: None,
+            cooldown: None,
+        };
+
+        match self.send(self.request(Method::POST, "v1/locks")?.json(&body)).await {
+            Ok(lock) => Ok(Some(LockGuard::new(self.clone(), lock, eta))),
+            Err(Error::Locked) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn get(&self, lock_id: &str) -> Result<ResponseProcess> {
+        self.send(self.request(Method::GET, &format!("v1/locks/{lock_id}"))?).await
+    }
+
+    pub async fn list(&self, filter: &ListLocks) -> Result<Page> {
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(app) = &filter.app {
+            query.push(("app", app.clone()));
+        }
+        if let Some(process) = &filter.process {
+            query.push(("process", process.clone()));
+        }
+        if !filter.statuses.is_empty() {
+            let statuses: Vec<String> = filter.statuses.iter().map(ToString::to_string).collect();
+            query.push(("status", statuses.join(",")));
+        }
+        if let Some(cursor) = &filter.cursor {
+            query.push(("cursor", cursor.clone()));
+        }
+        if let Some(limit) = filter.limit {
+            query.push(("limit", limit.to_string()));
+        }
+
+        let envelope: Envelope<Vec<ResponseProcess>> =
+            self.send_envelope(self.request(Method::GET, "v1/locks")?.query(&query)).await?;
+
+        Ok(Page {
+            locks: envelope.data,
+            next_cursor: envelope.meta.and_then(|m| m.next_cursor),
+        })
+    }
+
+    /// Follows the lock status until it's finished, starting with the current one.
+    pub async fn watch(&self, lock_id: &str) -> Result<Watch> {
+        let res = self
+            .request(Method::GET, &format!("v1/locks/{lock_id}/events"))?
+            .send()
+            .await?;
+
+        Ok(Watch::new(error_for_status(res).await?))
+    }
+
+    pub async fn set_status(&self, lock_id: &str, status: OperationStatus) -> Result<ResponseProcess> {
+        let req = self.request(Method::PATCH, &format!("v1/locks/{lock_id}"))?;
+
+        self.send(req.json(&UpdateProcess { status })).await
+    }
+
+    /// Moves the lock deadline to `eta` from now.
+    pub async fn heartbeat(&self, lock_id: &str, eta: Duration) -> Result<ResponseProcess> {
+        let req = self.request(Method::POST, &format!("v1/locks/{lock_id}/heartbeat"))?;
+
+        self.send(req.json(&Heartbeat { eta: format_eta(eta) })).await
+    }
+
+    /// Marks the lock `Completed`.
+    pub async fn release(&self, lock_id: &str) -> Result<ResponseProcess> {
+        self.send(self.request(Method::DELETE, &format!("v1/locks/{lock_id}"))?).await
+    }
+
+    fn request(&self, method: Method, path: &str) -> Result<RequestBuilder> {
+        let url = self
+            .base_url
+            .join(path)
+            .map_err(|_| Error::InvalidUrl(format!("{}{path}", self.base_url)))?;
+
+        Ok(self.http.request(method, url))
+    }
+
+    async fn send<T: DeserializeOwned>(&self, req: RequestBuilder) -> Result<T> {
+        Ok(self.send_envelope(req).await?.data)
+    }
+
+    async fn send_envelope<T: DeserializeOwned>(&self, req: RequestBuilder) -> Result<Envelope<T>> {
+        let res = error_for_status(req.send().await?).await?;
+
+        Ok(res.json::<Envelope<T>>().await?)
+    }
+}
+
+/// Turns an error envelope into [`Error::Api`], and `423 Locked` into [`Error::Locked`].
+async fn error_for_status(res: Response) -> Result<Response> {
+    let status = res.status();
+
+    if status.is_success() {
+        return Ok(res);
+    }
+
+    if status == StatusCode::LOCKED {
+        return Err(Error::Locked);
+    }
+
+    let detail = res
+        .json::<ErrorEnvelope>()
+        .await
+        .map(|e| e.error)
+        .unwrap_or_else(|_| ErrorDetail {
+            code: status.canonical_reason().unwrap_or_default().to_string(),
+            message: String::new(),
+        });
+
+    Err(Error::Api {
+        status: status.as_u16(),
+        code: detail.code,
+        message: detail.message,
+    })
+}
+
+/// The server counts in whole seconds.
+fn format_eta(eta: Duration) -> String {
+    format!("{}s", eta.as_secs().max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::extract::{Path, State};
+    use axum::http::StatusCode;
+    use axum::routing::{delete, post};
+    use axum::{Json, Router};
+    use chrono::Utc;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    const LOCK_ID: &str = "0190f7a4-5b8e-7c1a-9d3e-2f4b6a8c0e12";
+
+    #[derive(Default)]
+    struct Stub {
+        held: bool,
+        heartbeats: u32,
+        releases: u32,
+    }
+
+    type StubState = Arc<Mutex<Stub>>;
+
+    fn lock(status: OperationStatus) -> Json<serde_json::Value> {
+        let lock = ResponseProcess {
+            process_id: LOCK_ID.into(),
+            app: "billing".into(),
+            process_name: "invoices".into(),
+            status,
+            create_at: Utc::now(),
+            updated_at: Utc::now(),
+            ended_at: None,
+            sla: 1,
+        };
+
+        Json(serde_json::json!({ "data": lock }))
+    }
+
+    async fn create(State(stub): State<StubState>) -> (StatusCode, Json<serde_json::Value>) {
+        let mut stub = stub.lock().unwrap();
+        if stub.held {
+            let body = serde_json::json!({ "error": { "code": "ProcessExist", "message": "held" } });
+            return (StatusCode::LOCKED, Json(body));
+        }
+
+        stub.held = true;
+        (StatusCode::CREATED, lock(OperationStatus::New))
+    }
+
+    async fn heartbeat(State(stub): State<StubState>, Path(_): Path<String>) -> Json<serde_json::Value> {
+        stub.lock().unwrap().heartbeats += 1;
+        lock(OperationStatus::New)
+    }
+
+    async fn release(State(stub): State<StubState>, Path(_): Path<String>) -> Json<serde_json::Value> {
+        let mut stub = stub.lock().unwrap();
+        stub.held = false;
+        stub.releases += 1;
+        lock(OperationStatus::Completed)
+    }
+
+    /// Serves the stub under `prefix`, `""` for the root.
+    async fn serve(prefix: &str) -> (Client, StubState) {
+        let stub = StubState::default();
+        let routes = Router::new()
+            .route("/v1/locks", post(create))
+            .route("/v1/locks/:lock_id", delete(release))
+            .route("/v1/locks/:lock_id/heartbeat", post(heartbeat))
+            .with_state(stub.clone());
+        let app = match prefix {
+            "" => routes,
+            prefix => Router::new().nest(prefix, routes),
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = Client::new(&format!("http://{addr}{prefix}")).unwrap().with_retry_interval(Duration::from_millis(20));
+
+        (client, stub)
+    }
+
+    #[tokio::test]
+    async fn test_guard_heartbeats_and_releases_on_drop() {
+        let (client, stub) = serve("").await;
+        let eta = Duration::from_millis(300);
+
+        let guard = client.try_acquire("billing", "invoices", eta).await.unwrap().unwrap();
+        assert_eq!(guard.id(), LOCK_ID);
+        assert!(client.try_acquire("billing", "invoices", eta).await.unwrap().is_none());
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(stub.lock().unwrap().heartbeats >= 1);
+
+        drop(guard);
+
+        // `acquire` waits for the release sent by the dropped guard.
+        let guard = tokio::time::timeout(Duration::from_secs(2), client.acquire("billing", "invoices", eta))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stub.lock().unwrap().releases, 1);
+
+        let released = guard.release().await.unwrap();
+        assert_eq!(released.status, OperationStatus::Completed);
+        assert_eq!(stub.lock().unwrap().releases, 2);
+    }
+
+    #[tokio::test]
+    async fn test_keeps_the_base_url_path() {
+        let (client, stub) = serve("/flowlocker").await;
+
+        let guard = client.try_acquire("billing", "invoices", Duration::from_secs(60)).await.unwrap().unwrap();
+        guard.release().await.unwrap();
+        assert_eq!(stub.lock().unwrap().releases, 1);
+    }
+}
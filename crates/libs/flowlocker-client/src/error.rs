@@ -0,0 +1,39 @@
+use derive_more::From;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, From)]
+pub enum Error {
+    /// Someone else holds the lock.
+    Locked,
+    /// The server answered with an error envelope.
+    Api {
+        status: u16,
+        code: String,
+        message: String,
+    },
+    InvalidUrl(String),
+
+    #[from]
+    Http(reqwest::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Locked => write!(f, "lock is already held"),
+            Error::Api { status, code, message } => write!(f, "{code} ({status}): {message}"),
+            Error::InvalidUrl(url) => write!(f, "invalid server url: {url}"),
+            Error::Http(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use lib_models::lock::{OperationStatus, ResponseProcess};
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+
+/// Lowest pause between two heartbeats, whatever the eta.
+const MIN_HEARTBEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A held lock.
+///
+/// While the guard lives the lock deadline is pushed to `eta` from now every
+/// third of `eta`. Dropping the guard without [`LockGuard::release`] releases
+/// the lock in the background when a Tokio runtime is still around, otherwise
+/// the server outdates it once the SLA is over.
+#[derive(Debug)]
+pub struct LockGuard {
+    client: Client,
+    lock: ResponseProcess,
+    heartbeat: Option<JoinHandle<()>>,
+}
+
+impl LockGuard {
+    pub(crate) fn new(client: Client, lock: ResponseProcess, eta: Duration) -> Self {
+        let heartbeat = tokio::spawn(heartbeat(client.clone(), lock.process_id.to_string(), eta));
+
+        LockGuard {
+            client,
+            lock,
+            heartbeat: Some(heartbeat),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.lock.process_id
+    }
+
+    /// The lock as of the last call made through this guard.
+    pub fn lock(&self) -> &ResponseProcess {
+        &self.lock
+    }
+
+    /// Setting a final status stops the heartbeats, the guard won't release the lock on drop then.
+    pub async fn set_status(&mut self, status: OperationStatus) -> Result<&ResponseProcess> {
+        self.lock = self.client.set_status(self.id(), status).await?;
+
+        if self.lock.status.is_finished() {
+            self.stop_heartbeat();
+        }
+
+        Ok(&self.lock)
+    }
+
+    pub async fn release(mut self) -> Result<ResponseProcess> {
+        self.stop_heartbeat();
+
+        self.client.release(self.id()).await
+    }
+
+    /// Stops the heartbeats and leaves the lock held, it's outdated once the SLA is over
+    /// unless released by id.
+    pub fn detach(mut self) -> ResponseProcess {
+        self.stop_heartbeat();
+
+        self.lock.clone()
+    }
+
+    fn stop_heartbeat(&mut self) {
+        if let Some(heartbeat) = self.heartbeat.take() {
+            heartbeat.abort();
+        }
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        // Released or finished through the guard already.
+        let Some(heartbeat) = self.heartbeat.take() else { return };
+        heartbeat.abort();
+
+        let Ok(runtime) = Handle::try_current() else {
+            warn!("no runtime to release lock {}, it will be outdated", self.id());
+            return;
+        };
+
+        let client = self.client.clone();
+        let id = self.id().to_string();
+        runtime.spawn(async move {
+            if let Err(e) = client.release(&id).await {
+                warn!("can't release lock {}: {}", id, e);
+            }
+        });
+    }
+}
+
+async fn heartbeat(client: Client, id: String, eta: Duration) {
+    let mut interval = tokio::time::interval((eta / 3).max(MIN_HEARTBEAT_INTERVAL));
+    // The first tick completes right away, the lock was just acquired.
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        match client.heartbeat(&id, eta).await {
+            Ok(_) => debug!("lock {} renewed", id),
+            // Finished or removed behind our back, nothing left to renew.
+            Err(Error::Api { status: 400 | 404, .. }) => {
+                warn!("lock {} is gone, heartbeats stopped", id);
+                return;
+            }
+            Err(e) => warn!("can't renew lock {}: {}", id, e),
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Async client for the flowlocker `/v1` REST API.
+//!
+//! ```ignore
+//! let client = Client::new("http://localhost:8050")?;
+//! let guard = client.acquire("billing", "invoices", Duration::from_secs(60)).await?;
+//! // ... the lock is renewed in the background while the work runs ...
+//! guard.release().await?;
+//! ```
+
+mod client;
+pub mod error;
+mod guard;
+mod watch;
+
+pub use client::{Client, ListLocks, Page, DEFAULT_RETRY_INTERVAL};
+pub use guard::LockGuard;
+pub use watch::Watch;
+pub use lib_models::lock::{OperationStatus, ResponseProcess};
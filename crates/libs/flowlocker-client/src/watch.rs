@@ -0,0 +1,75 @@
+use lib_models::lock::ResponseProcess;
+use reqwest::Response;
+
+use crate::error::Result;
+
+/// Status changes of one lock, read from its server-sent event stream.
+#[derive(Debug)]
+pub struct Watch {
+    res: Response,
+    buf: String,
+}
+
+impl Watch {
+    pub(crate) fn new(res: Response) -> Self {
+        Watch { res, buf: String::new() }
+    }
+
+    /// The next status of the lock, `None` once it's finished and the server closed the stream.
+    pub async fn next(&mut self) -> Result<Option<ResponseProcess>> {
+        loop {
+            while let Some(end) = self.buf.find("\n\n") {
+                let event: String = self.buf.drain(..end + 2).collect();
+
+                if let Some(lock) = parse_status_event(&event) {
+                    return Ok(Some(lock));
+                }
+            }
+
+            match self.res.chunk().await? {
+                Some(chunk) => self.buf.push_str(&String::from_utf8_lossy(&chunk)),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Only `status` events carry a lock, keep-alive comments and `lagged` events are skipped.
+fn parse_status_event(event: &str) -> Option<ResponseProcess> {
+    let mut name = None;
+    let mut data = String::new();
+
+    for line in event.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            name = Some(value.trim());
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push_str(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+
+    match name {
+        Some("status") => serde_json::from_str(&data).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status_event() {
+        let event = concat!(
+            "event: status\n",
+            r#"data: {"process_id":"0190f7a4-5b8e-7c1a-9d3e-2f4b6a8c0e12","app":"billing","process_name":"invoices","#,
+            r#""status":"InProgress","create_at":"2024-07-01T10:00:00Z","updated_at":"2024-07-01T10:00:05Z","sla":60}"#,
+            "\nid: 0190f7a4-5b8e-7c1a-9d3e-2f4b6a8c0e12:1719828005\n\n",
+        );
+
+        let lock = parse_status_event(event).unwrap();
+        assert_eq!(lock.status, lib_models::lock::OperationStatus::InProgress);
+
+        assert!(parse_status_event(":\n\n").is_none());
+        assert!(parse_status_event("event: lagged\ndata: 3\n\n").is_none());
+    }
+}
@@ -92,3 +92,9 @@ pub fn init_opentelemetry(tracer_name: String) -> Result<trace::Tracer> {
 
     Ok(tracer)
 }
+
+/// Exports the spans still queued in the batch processor. Blocks until the
+/// exporter is done, so call it off the async runtime threads.
+pub fn shutdown_opentelemetry() {
+    global::shutdown_tracer_provider();
+}
@@ -0,0 +1,3 @@
+//! Lock types shared by the server and its clients.
+
+pub mod lock;
@@ -0,0 +1,111 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ResponseProcess {
+    #[schema(value_type = String, format = Uuid)]
+    pub process_id: Cow<'static, str>,
+    #[schema(value_type = String)]
+    pub app: Cow<'static, str>,
+    #[schema(value_type = String)]
+    pub process_name: Cow<'static, str>,
+    pub status: OperationStatus,
+    pub create_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Seconds the process may run before the cleaner marks it `Outdated`.
+    pub sla: u64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, ToSchema)]
+pub enum OperationStatus {
+    New,
+    InProgress,
+    Completed,
+    Canceled,
+    Outdated,
+}
+
+impl OperationStatus {
+    pub fn is_canceled(&self) -> bool {
+        *self == OperationStatus::Canceled
+    }
+
+    pub fn is_completed(&self) -> bool {
+        *self == OperationStatus::Completed
+    }
+
+    /// Completed, canceled or outdated: the process won't change status anymore.
+    pub fn is_finished(&self) -> bool {
+        self.is_completed() || self.is_canceled() || self.is_outdated()
+    }
+
+    pub fn is_outdated(&self) -> bool {
+        *self == OperationStatus::Outdated
+    }
+}
+
+impl std::fmt::Display for OperationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationStatus::New => write!(f, "New"),
+            OperationStatus::Completed => write!(f, "Completed"),
+            OperationStatus::InProgress => write!(f, "InProgress"),
+            OperationStatus::Outdated => write!(f, "Outdated"),
+            OperationStatus::Canceled => write!(f, "Canceled"),
+        }
+    }
+}
+
+impl std::str::FromStr for OperationStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "New" => Ok(OperationStatus::New),
+            "Completed" => Ok(OperationStatus::Completed),
+            "InProgress" => Ok(OperationStatus::InProgress),
+            "Outdated" => Ok(OperationStatus::Outdated),
+            "Canceled" => Ok(OperationStatus::Canceled),
+            _ => Err(format!("unknown operation status: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewProcess {
+    pub app: String,
+    pub process: String,
+    /// How long the lock may be held: `<n>s`, `<n>m` or `<n>h`.
+    #[schema(example = "30m")]
+    pub eta: String,
+    /// Once a run with this key completed, acquisitions with it are refused for `window`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "report:2026-10-18")]
+    pub dedupe_key: Option<String>,
+    /// How long after its completion a run holds its `dedupe_key`: `<n>s`, `<n>m` or `<n>h`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "24h")]
+    pub window: Option<String>,
+    /// How long after this run ends the process can't be acquired again: `<n>s`, `<n>m` or `<n>h`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "10m")]
+    pub cooldown: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateProcess {
+    pub status: OperationStatus,
+}
+
+/// Body of `POST /v1/locks/{lock_id}/heartbeat`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Heartbeat {
+    /// The lock deadline moves to this long from now: `<n>s`, `<n>m` or `<n>h`.
+    #[schema(example = "30m")]
+    pub eta: String,
+}
@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use serde::ser::{Serialize, Serializer, SerializeStruct};
+use serde::Deserialize;
 
 pub type Segment<'a> = Cow<'a, str>;
 
@@ -9,24 +10,54 @@ pub enum Parameter {
     StringArg(String),
     BoolArg(bool),
     IntArg(i32),
+    UIntArg(u64),
+    ListArg(Vec<Parameter>),
 }
 
 pub enum Conditions {
     Eq,
+    Neq,
     Gt,
     Gte,
     Lt,
     Lte,
+    Inside,
 }
 
 impl Conditions {
     fn to_string(&self) -> Cow<'static, str> {
         match self {
             Conditions::Eq => Cow::Borrowed("="),
+            Conditions::Neq => Cow::Borrowed("!="),
             Conditions::Gt => Cow::Borrowed(">"),
             Conditions::Gte => Cow::Borrowed(">="),
             Conditions::Lt => Cow::Borrowed("<"),
             Conditions::Lte => Cow::Borrowed("<="),
+            Conditions::Inside => Cow::Borrowed("INSIDE"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, serde::Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl Order {
+    fn as_sql(self) -> Cow<'static, str> {
+        match self {
+            Order::Asc => Cow::Borrowed("ASC"),
+            Order::Desc => Cow::Borrowed("DESC"),
+        }
+    }
+
+    fn seek_condition(&self) -> Conditions {
+        match self {
+            Order::Asc => Conditions::Gt,
+            Order::Desc => Conditions::Lt,
         }
     }
 }
@@ -53,6 +84,8 @@ impl Serialize for Parameter {
             Parameter::StringArg(ref value) => serializer.serialize_str(value),
             Parameter::BoolArg(value) => serializer.serialize_bool(value),
             Parameter::IntArg(value) => serializer.serialize_i32(value),
+            Parameter::UIntArg(value) => serializer.serialize_u64(value),
+            Parameter::ListArg(ref values) => serializer.collect_seq(values),
         }
     }
 }
@@ -106,17 +139,8 @@ impl<'a> QueryBuilder<'a> {
         Parameter: From<T>,
         C: ToString,
     {
-        let (new_condition, new_parameter) = condition(&column, conditions, param);
-
-        let where_segment: Segment = "WHERE".into();
-
-        if self.segments.contains(&where_segment) {
-            self.add_segment_p("AND", new_condition);
-            self.param(column.to_string(), new_parameter);
-        } else {
-            self.add_segment_p("WHERE", new_condition);
-            self.param(column.to_string(), new_parameter);
-        }
+        let prefix = self.where_or_and();
+        self.push_condition(prefix, column.to_string(), conditions, param.into());
 
         self
     }
@@ -127,15 +151,92 @@ impl<'a> QueryBuilder<'a> {
         Parameter: From<T>,
         C: ToString,
     {
-        let (new_con, p) = condition(&column, conditions, param);
+        self.push_condition("AND", column.to_string(), conditions, param.into());
+
+        self
+    }
+
+    /// Keyset pagination: keeps only rows that come after (`value`, `id`) in `order`,
+    /// using `id_column` as the tie-breaker for equal `column` values.
+    pub fn seek<C, I>(mut self, column: C, value: Parameter, id_column: I, id: Parameter, order: Order) -> Self
+    where
+        C: ToString,
+        I: ToString,
+    {
+        let (column, id_column) = (column.to_string(), id_column.to_string());
+        let value_key = self.placeholder("seek_value");
+        let id_key = self.placeholder("seek_id");
+        let cmp = order.seek_condition().to_string();
 
+        let seek = format!(
+            "({column} {cmp} ${value_key} OR ({column} = ${value_key} AND {id_column} {cmp} ${id_key}))"
+        );
 
-        self.add_segment_p("AND", new_con);
-        self.param(column.to_string(), p);
+        let prefix = self.where_or_and();
+        self.add_segment_p(prefix, seek);
+        self.param(value_key, value);
+        self.param(id_key, id);
 
         self
     }
 
+    /// Sorts by each `(column, order)` pair in turn.
+    pub fn order_by<C: ToString>(mut self, columns: &[(C, Order)]) -> Self {
+        let columns = columns
+            .iter()
+            .map(|(c, o)| format!("{} {}", c.to_string(), o.as_sql()))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        self.add_segment_p("ORDER BY", columns);
+
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.add_segment_p("LIMIT", limit.to_string());
+
+        self
+    }
+
+    pub fn start(mut self, start: u32) -> Self {
+        self.add_segment_p("START", start.to_string());
+
+        self
+    }
+
+    fn where_or_and(&self) -> &'a str {
+        let where_segment: Segment = "WHERE".into();
+
+        if self.segments.contains(&where_segment) {
+            "AND"
+        } else {
+            "WHERE"
+        }
+    }
+
+    fn push_condition(&mut self, prefix: &'a str, column: String, conditions: Conditions, param: Parameter) {
+        let key = self.placeholder(&column);
+        let new_condition = format!("{} {} ${}", column, conditions.to_string(), key);
+
+        self.add_segment_p(prefix, new_condition);
+        self.param(key, param);
+    }
+
+    // Same column filtered twice (e.g. a time range) must not share a placeholder.
+    fn placeholder(&self, column: &str) -> String {
+        if !self.params.contains_key(column) {
+            return column.to_string();
+        }
+
+        let mut n = 2;
+        while self.params.contains_key(&format!("{column}_{n}")) {
+            n += 1;
+        }
+
+        format!("{column}_{n}")
+    }
+
     pub fn or<T: Into<Segment<'a>>>(mut self, condition: T, placeholder: String, param: Parameter) -> Self {
         self.add_segment_p("OR", condition);
         self.param(placeholder, param);
@@ -217,6 +318,16 @@ impl From<i32> for Parameter {
     fn from(i: i32) -> Self { Parameter::IntArg(i) }
 }
 
+impl From<u64> for Parameter {
+    fn from(i: u64) -> Self { Parameter::UIntArg(i) }
+}
+
+impl<T: Into<Parameter>> From<Vec<T>> for Parameter {
+    fn from(v: Vec<T>) -> Self {
+        Parameter::ListArg(v.into_iter().map(Into::into).collect())
+    }
+}
+
 impl From<Parameter> for String {
     fn from(arg: Parameter) -> Self {
         match arg {
@@ -247,7 +358,62 @@ impl From<Parameter> for i32 {
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+
+    #[test]
+    fn test_range_filter_gets_distinct_placeholders() {
+        let (query, args) = QueryBuilder::default()
+            .select("*")
+            .from("type::table($table)", Parameter::StringArg("process".to_string()))
+            .filter("create_at", Conditions::Gte, 10u64)
+            .filter("create_at", Conditions::Lt, 20u64)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            query,
+            "SELECT * FROM type::table($table) WHERE create_at >= $create_at AND create_at < $create_at_2"
+        );
+        assert_eq!(args.get("create_at"), Some(&Parameter::UIntArg(10)));
+        assert_eq!(args.get("create_at_2"), Some(&Parameter::UIntArg(20)));
+    }
+
+    #[test]
+    fn test_inside_binds_list() {
+        let (query, args) = QueryBuilder::default()
+            .select("*")
+            .from("type::table($table)", Parameter::StringArg("process".to_string()))
+            .filter("status", Conditions::Inside, vec!["New", "InProgress"])
+            .build()
+            .unwrap();
+
+        assert_eq!(query, "SELECT * FROM type::table($table) WHERE status INSIDE $status");
+        assert_eq!(
+            serde_json::to_string(args.get("status").unwrap()).unwrap(),
+            r#"["New","InProgress"]"#
+        );
+    }
+
+    #[test]
+    fn test_seek_order_and_limit() {
+        let (query, args) = QueryBuilder::default()
+            .select("*")
+            .from("type::table($table)", Parameter::StringArg("process".to_string()))
+            .filter("app", Conditions::Eq, "billing")
+            .seek("create_at", 10u64.into(), "process_id", "abc".into(), Order::Desc)
+            .order_by(&[("create_at", Order::Desc), ("process_id", Order::Desc)])
+            .limit(50)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            query,
+            "SELECT * FROM type::table($table) WHERE app = $app \
+             AND (create_at < $seek_value OR (create_at = $seek_value AND process_id < $seek_id)) \
+             ORDER BY create_at DESC, process_id DESC LIMIT 50"
+        );
+        assert_eq!(args.get("seek_id"), Some(&Parameter::StringArg("abc".to_string())));
+    }
 
     // #[test]
     // fn test_new() {